@@ -0,0 +1,95 @@
+use crate::{Error, Location};
+
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Part of config is ignored, but remaining parts are applied
+    Warning,
+    /// Whole element or file is ignored
+    Error,
+}
+
+/// A problem found while merging configs which doesn't stop merging
+#[derive(Debug)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Location of the ignored element
+    pub location: Location,
+    pub error: Error,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+
+        write!(f, "{}: {}: {}", self.location, severity, self.error.kind())
+    }
+}
+
+/// Collected [`Diagnostic`]s in order they are found
+///
+/// See [`crate::FontConfig::merge_config_with_diagnostics`]
+#[derive(Debug, Default)]
+pub struct Diagnostics {
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Diagnostics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.diagnostics.len()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Diagnostic> {
+        self.diagnostics.iter()
+    }
+
+    /// Return `true` if there's any [`Severity::Error`] diagnostic
+    pub fn has_errors(&self) -> bool {
+        self.iter().any(|d| d.severity == Severity::Error)
+    }
+
+    /// Add a diagnostic, location of `error` is used when it has one
+    pub(crate) fn push(&mut self, severity: Severity, location: Location, error: Error) {
+        let location = error.location().cloned().unwrap_or(location);
+        let diagnostic = Diagnostic {
+            severity,
+            location,
+            error,
+        };
+
+        #[cfg(feature = "log")]
+        log::warn!("{}", diagnostic);
+
+        self.diagnostics.push(diagnostic);
+    }
+}
+
+impl IntoIterator for Diagnostics {
+    type Item = Diagnostic;
+    type IntoIter = std::vec::IntoIter<Diagnostic>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.diagnostics.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Diagnostics {
+    type Item = &'a Diagnostic;
+    type IntoIter = std::slice::Iter<'a, Diagnostic>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.diagnostics.iter()
+    }
+}
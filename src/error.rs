@@ -1,18 +1,94 @@
+use crate::Location;
+
 use std::fmt;
 use std::num::ParseFloatError;
 use std::num::ParseIntError;
+use std::path::Path;
 use std::str::ParseBoolError;
+use std::sync::Arc;
 
 #[derive(Debug)]
 pub enum Error {
     Xml(roxmltree::Error),
     NoFontconfig,
-    InvalidFormat(String),
+    InvalidFormat(FormatError),
     IoError(std::io::Error),
     ParseEnumError(&'static str, String),
     ParseIntError(ParseIntError),
     ParseFloatError(ParseFloatError),
     ParseBoolError(ParseBoolError),
+    /// Formatted config isn't parsed into the same parts
+    SemanticsChanged,
+    /// Error with the position where it occurred
+    Located(Box<Error>, Location),
+}
+
+/// Kind of [`Error::InvalidFormat`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FormatError {
+    /// Element has no text
+    MissingText,
+    /// Operator has fewer operands than needed
+    MissingExpression,
+    /// `<range>` has fewer than two `<int>`
+    MissingInt,
+    /// `<test>` has no value
+    EmptyTest,
+    /// `<edit>` has no value
+    EmptyEdit,
+    /// Expect `<int>` or `<range>` but got other element
+    ExpectIntOrRange(String),
+    /// Code point in `<charset>` is negative
+    NegativeCodePoint(crate::Int),
+    /// Unknown expression element
+    UnknownExpression(String),
+    /// Unknown element, only reported in strict mode
+    UnknownElement(String),
+    /// Unknown attribute `.1` of element `.0`, only reported in strict mode
+    UnknownAttribute(String, String),
+    /// Element `.1` is not allowed in element `.0`, only reported in strict mode
+    UnexpectedElement(String, String),
+    /// Element doesn't allow text content, only reported in strict mode
+    UnexpectedText(String),
+}
+
+impl Error {
+    /// Location of the error, if known
+    pub fn location(&self) -> Option<&Location> {
+        match self {
+            Error::Located(_, location) => Some(location),
+            _ => None,
+        }
+    }
+
+    /// Error without location
+    pub fn kind(&self) -> &Error {
+        match self {
+            Error::Located(e, _) => e.kind(),
+            e => e,
+        }
+    }
+
+    /// Attach location of `node` unless error already has more precise one
+    pub(crate) fn at(self, node: roxmltree::Node) -> Self {
+        match self {
+            Error::Located(..) => self,
+            e => Error::Located(Box::new(e), Location::from_node(node, None)),
+        }
+    }
+
+    /// Fill file of the location
+    pub(crate) fn with_file(self, file: Option<&Arc<Path>>) -> Self {
+        match self {
+            Error::Located(e, mut location) => {
+                if location.file.is_none() {
+                    location.file = file.cloned();
+                }
+                Error::Located(e, location)
+            }
+            e => e,
+        }
+    }
 }
 
 impl From<std::io::Error> for Error {
@@ -56,8 +132,33 @@ impl fmt::Display for Error {
             Error::ParseIntError(e) => e.fmt(f),
             Error::ParseFloatError(e) => e.fmt(f),
             Error::ParseBoolError(e) => e.fmt(f),
+            Error::SemanticsChanged => write!(f, "Formatting changes meaning of config"),
+            Error::Located(e, location) => write!(f, "{}: {}", location, e),
         }
     }
 }
 
 impl std::error::Error for Error {}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormatError::MissingText => write!(f, "Can't get text"),
+            FormatError::MissingExpression => write!(f, "Expect expression"),
+            FormatError::MissingInt => write!(f, "Expect int"),
+            FormatError::EmptyTest => write!(f, "Empty test value"),
+            FormatError::EmptyEdit => write!(f, "Empty edit value"),
+            FormatError::ExpectIntOrRange(name) => write!(f, "Expect IntOrRange: {:?}", name),
+            FormatError::NegativeCodePoint(c) => write!(f, "Negative code point: {}", c),
+            FormatError::UnknownExpression(name) => write!(f, "Unknown expression: {:?}", name),
+            FormatError::UnknownElement(name) => write!(f, "Unknown element: {:?}", name),
+            FormatError::UnknownAttribute(name, attr) => {
+                write!(f, "Unknown attribute of {:?}: {:?}", name, attr)
+            }
+            FormatError::UnexpectedElement(name, child) => {
+                write!(f, "Unexpected element in {:?}: {:?}", name, child)
+            }
+            FormatError::UnexpectedText(name) => write!(f, "Unexpected text in {:?}", name),
+        }
+    }
+}
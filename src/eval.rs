@@ -0,0 +1,366 @@
+//! Evaluate [`Expression`] trees
+//!
+//! This follows `FcConfigEvaluate` and `FcConfigCompareValue` of freedesktop.org's Fontconfig library.
+
+use crate::util::eq_ignore_blanks_and_case;
+use crate::*;
+
+/// Patterns which `<name>` elements are resolved against
+///
+/// `p` is the pattern being edited, `p_pat` is the original pattern when `kind` is [`MatchTarget::Font`]
+#[derive(Clone, Copy)]
+pub(crate) struct Context<'a> {
+    pub kind: MatchTarget,
+    pub p: &'a Pattern,
+    pub p_pat: Option<&'a Pattern>,
+}
+
+impl Expression {
+    /// Evaluate expression as a value of property `kind`
+    ///
+    /// `<name>` elements are looked up in `pattern`, or in `font` for `target="font"` when `font` is given.
+    /// Returns `None` when the value can't be computed, e.g. looked up property doesn't exist or operand types mismatch.
+    pub fn eval(
+        &self,
+        kind: &PropertyKind,
+        pattern: &Pattern,
+        font: Option<&Pattern>,
+    ) -> Option<Value> {
+        let ctx = match font {
+            Some(font) => Context {
+                kind: MatchTarget::Font,
+                p: font,
+                p_pat: Some(pattern),
+            },
+            None => Context {
+                kind: MatchTarget::Pattern,
+                p: pattern,
+                p_pat: None,
+            },
+        };
+
+        eval(ctx, kind, self)
+    }
+}
+
+/// Evaluate `expr` for property `kind`
+pub(crate) fn eval(ctx: Context, kind: &PropertyKind, expr: &Expression) -> Option<Value> {
+    match expr {
+        Expression::Simple(Value::Constant(c)) => c.get_value(kind.clone()).map(Value::Int),
+        Expression::Simple(Value::Property(target, object)) => {
+            let p = match (ctx.kind, target) {
+                (MatchTarget::Font, PropertyTarget::Pattern) => ctx.p_pat?,
+                (MatchTarget::Pattern, PropertyTarget::Font) => return None,
+                _ => ctx.p,
+            };
+            p.get_value(object).cloned()
+        }
+        Expression::Simple(value) => Some(value.clone()),
+        Expression::Matrix(exprs) => {
+            let mut matrix = [0.0; 4];
+            for (m, expr) in matrix.iter_mut().zip(exprs.iter()) {
+                *m = eval(ctx, kind, expr)?.as_double()?;
+            }
+            Some(Value::Matrix(matrix))
+        }
+        Expression::Unary(op, expr) => eval_unary(*op, eval(ctx, kind, expr)?),
+        Expression::WithUnknownAttributes(expr, _) => eval(ctx, kind, expr),
+        Expression::Binary(op, exprs) => {
+            let [left, right] = &**exprs;
+            let (left, right) = (eval(ctx, kind, left)?, eval(ctx, kind, right)?);
+            Some(Value::Bool(compare_value(&left, *op, &right)))
+        }
+        Expression::Ternary(TernaryOp::If, exprs) => {
+            let [cond, then, otherwise] = &**exprs;
+            match eval(ctx, kind, cond)? {
+                Value::Bool(true) => eval(ctx, kind, then),
+                Value::Bool(false) => eval(ctx, kind, otherwise),
+                _ => None,
+            }
+        }
+        // Fontconfig nests list operands to the right, `<minus>a b c</minus>` is `a - (b - c)`
+        Expression::List(op, exprs) => {
+            let (last, rest) = exprs.split_last()?;
+            let mut right = eval(ctx, kind, last);
+            for left in rest.iter().rev() {
+                right = Some(eval_list_op(*op, eval(ctx, kind, left), right)?);
+            }
+            right
+        }
+    }
+}
+
+fn eval_unary(op: UnaryOp, value: Value) -> Option<Value> {
+    let round: fn(Double) -> Double = match (op, &value) {
+        (UnaryOp::Not, Value::Bool(b)) => return Some(Value::Bool(!b)),
+        (UnaryOp::Not, _) => return None,
+        (_, Value::Int(_)) => return Some(value),
+        (UnaryOp::Floor, _) => Double::floor,
+        (UnaryOp::Cecil, _) => Double::ceil,
+        // FcDoubleRound
+        (UnaryOp::Round, _) => |d| (d + 0.5).floor(),
+        (UnaryOp::Trunc, _) => Double::trunc,
+    };
+
+    match value {
+        Value::Double(d) => Some(double_to_value(round(d))),
+        _ => None,
+    }
+}
+
+/// Operands are `None` when they can't be evaluated
+fn eval_list_op(op: ListOp, left: Option<Value>, right: Option<Value>) -> Option<Value> {
+    // void is promoted to the identity matrix
+    let (left, right) = match (left, right) {
+        (None, Some(right @ Value::Matrix(_))) => (Value::Matrix(IDENTITY_MATRIX), right),
+        (Some(left @ Value::Matrix(_)), None) => (left, Value::Matrix(IDENTITY_MATRIX)),
+        (left, right) => (left?, right?),
+    };
+
+    match (op, left, right) {
+        (ListOp::Or, Value::Bool(l), Value::Bool(r)) => Some(Value::Bool(l || r)),
+        (ListOp::And, Value::Bool(l), Value::Bool(r)) => Some(Value::Bool(l && r)),
+        (ListOp::Plus, Value::String(l), Value::String(r)) => Some(Value::String(l + &r)),
+        (ListOp::Times, Value::Matrix(l), Value::Matrix(r)) => {
+            Some(Value::Matrix(matrix_multiply(&l, &r)))
+        }
+        (ListOp::Plus, Value::CharSet(l), Value::CharSet(r)) => Some(Value::CharSet(l.union(&r))),
+        (ListOp::Minus, Value::CharSet(l), Value::CharSet(r)) => {
+            Some(Value::CharSet(l.subtract(&r)))
+        }
+        (op, left, right) => {
+            let (l, r) = (left.as_double()?, right.as_double()?);
+            let d = match op {
+                ListOp::Plus => l + r,
+                ListOp::Minus => l - r,
+                ListOp::Times => l * r,
+                ListOp::Divide => l / r,
+                ListOp::Or | ListOp::And => return None,
+            };
+            Some(double_to_value(d))
+        }
+    }
+}
+
+/// Integral doubles are demoted to [`Value::Int`]
+fn double_to_value(d: Double) -> Value {
+    if d.fract() == 0.0 && d >= Int::MIN as Double && d <= Int::MAX as Double {
+        Value::Int(d as Int)
+    } else {
+        Value::Double(d)
+    }
+}
+
+const IDENTITY_MATRIX: Matrix = [1.0, 0.0, 0.0, 1.0];
+
+fn matrix_multiply(a: &Matrix, b: &Matrix) -> Matrix {
+    let [axx, axy, ayx, ayy] = *a;
+    let [bxx, bxy, byx, byy] = *b;
+
+    [
+        axx * bxx + axy * byx,
+        axx * bxy + axy * byy,
+        ayx * bxx + ayy * byx,
+        ayx * bxy + ayy * byy,
+    ]
+}
+
+/// [`compare_value`] with `FcOpFlagIgnoreBlanks`, strings are `eq` when they only differ in blanks and case
+pub(crate) fn compare_value_ignore_blanks(left: &Value, op: BinaryOp, right: &Value) -> bool {
+    match (left, op, right) {
+        (Value::String(l), BinaryOp::Eq, Value::String(r)) => eq_ignore_blanks_and_case(l, r),
+        (Value::String(l), BinaryOp::NotEq, Value::String(r)) => !eq_ignore_blanks_and_case(l, r),
+        _ => compare_value(left, op, right),
+    }
+}
+
+/// [`compare_value`] with `FcOpListing`, used to match `<acceptfont>` and `<rejectfont>` patterns
+///
+/// Strings are equal when they only differ in blanks and case, and language sets only need to contain `right`.
+pub(crate) fn compare_value_listing(left: &Value, right: &Value) -> bool {
+    match (left, right) {
+        (Value::LangSet(_), Value::LangSet(_) | Value::String(_))
+        | (Value::String(_), Value::LangSet(_)) => compare_value(left, BinaryOp::Contains, right),
+        _ => compare_value_ignore_blanks(left, BinaryOp::Eq, right),
+    }
+}
+
+/// Compare pattern value `left` with test value `right`
+///
+/// Integers are promoted to doubles and doubles to ranges when compared with a range,
+/// and strings are promoted to language sets when compared with a language set.
+/// Values with different types are only `not_eq` and `not_contains`.
+pub(crate) fn compare_value(left: &Value, op: BinaryOp, right: &Value) -> bool {
+    use BinaryOp::*;
+
+    match (left, right) {
+        (Value::Int(_) | Value::Double(_), Value::Int(_) | Value::Double(_)) => {
+            let (l, r) = (left.as_double().unwrap(), right.as_double().unwrap());
+            match op {
+                Eq | Contains => l == r,
+                NotEq | NotContains => l != r,
+                Less => l < r,
+                LessEq => l <= r,
+                More => l > r,
+                MoreEq => l >= r,
+            }
+        }
+        (Value::Bool(l), Value::Bool(r)) => match op {
+            Eq | Contains => l == r,
+            NotEq | NotContains => l != r,
+            _ => false,
+        },
+        (Value::Matrix(l), Value::Matrix(r)) => match op {
+            Eq | Contains => l == r,
+            NotEq | NotContains => l != r,
+            _ => false,
+        },
+        (Value::LangSet(_), Value::LangSet(_) | Value::String(_))
+        | (Value::String(_), Value::LangSet(_)) => {
+            let (l, r) = (left.as_langset().unwrap(), right.as_langset().unwrap());
+            match op {
+                Eq => l == r,
+                NotEq => l != r,
+                Contains => l.contains_all(&r),
+                NotContains => !l.contains_all(&r),
+                _ => false,
+            }
+        }
+        (Value::String(l), Value::String(r)) => match op {
+            Eq => l.eq_ignore_ascii_case(r),
+            NotEq => !l.eq_ignore_ascii_case(r),
+            Contains => l.to_lowercase().contains(&r.to_lowercase()),
+            NotContains => !l.to_lowercase().contains(&r.to_lowercase()),
+            _ => false,
+        },
+        (Value::Range(..) | Value::Int(_) | Value::Double(_), Value::Range(..))
+        | (Value::Range(..), Value::Int(_) | Value::Double(_)) => {
+            let (lb, le) = left.as_range().unwrap();
+            let (rb, re) = right.as_range().unwrap();
+            match op {
+                Eq => lb == rb && le == re,
+                NotEq => !(lb == rb && le == re),
+                Contains => lb <= rb && re <= le,
+                NotContains => !(lb <= rb && re <= le),
+                Less => le < rb,
+                LessEq => le <= rb,
+                More => lb > re,
+                MoreEq => lb >= re,
+            }
+        }
+        (Value::CharSet(l), Value::CharSet(r)) => {
+            let subset = r.is_subset(l);
+            match op {
+                Eq => l == r,
+                NotEq => l != r,
+                Contains => subset,
+                NotContains => !subset,
+                _ => false,
+            }
+        }
+        _ => matches!(op, NotEq | NotContains),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval_str(s: &str, kind: PropertyKind, pattern: &Pattern, font: &Pattern) -> Option<Value> {
+        let parts = parse_config_parts(&format!(
+            "<fontconfig><match><edit name=\"x\">{}</edit></match></fontconfig>",
+            s
+        ))
+        .unwrap();
+        match &parts[0] {
+            ConfigPart::Match(m) => m.edits[0].value.expr().eval(&kind, pattern, Some(font)),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn eval_scale_factor() {
+        let pattern: Pattern = vec![(PropertyKind::PixelSize, Value::Double(24.0))]
+            .into_iter()
+            .collect();
+        let font: Pattern = vec![(PropertyKind::PixelSize, Value::Int(16))]
+            .into_iter()
+            .collect();
+
+        let factor = eval_str(
+            "<divide><name target=\"pattern\">pixelsize</name><name target=\"font\">pixelsize</name></divide>",
+            PropertyKind::PixelSizeFixupFactor,
+            &pattern,
+            &font,
+        );
+        k9::assert_equal!(factor, Some(Value::Double(1.5)));
+
+        // missing matrix is promoted to identity
+        let matrix = eval_str(
+            "<times><name>matrix</name><matrix><double>2</double><int>0</int><int>0</int><double>2</double></matrix></times>",
+            PropertyKind::Matrix,
+            &pattern,
+            &font,
+        );
+        k9::assert_equal!(matrix, Some(Value::Matrix([2.0, 0.0, 0.0, 2.0])));
+    }
+
+    #[test]
+    fn eval_ops() {
+        let pattern = Pattern::new();
+        let font = Pattern::new();
+
+        k9::assert_equal!(
+            eval_str(
+                "<minus><int>10</int><int>4</int><int>3</int></minus>",
+                PropertyKind::Size,
+                &pattern,
+                &font,
+            ),
+            Some(Value::Int(9))
+        );
+        k9::assert_equal!(
+            eval_str(
+                "<round><double>2.5</double></round>",
+                PropertyKind::Size,
+                &pattern,
+                &font,
+            ),
+            Some(Value::Int(3))
+        );
+        k9::assert_equal!(
+            eval_str(
+                "<if><less><const>bold</const><int>300</int></less><string>a</string><string>b</string></if>",
+                PropertyKind::Weight,
+                &pattern,
+                &font,
+            ),
+            Some(Value::String("a".into()))
+        );
+        k9::assert_equal!(
+            eval_str(
+                "<plus><charset><range><int>0</int><int>10</int></range></charset><charset><int>11</int></charset></plus>",
+                PropertyKind::Charset,
+                &pattern,
+                &font,
+            ),
+            Some(Value::CharSet(CharSet::from_ranges([(0, 11)])))
+        );
+    }
+
+    #[test]
+    fn compare_langset() {
+        let font = Value::LangSet("en|ko-KR".into());
+
+        assert!(compare_value(&font, BinaryOp::Contains, &"ko_kr".into()));
+        assert!(compare_value(&font, BinaryOp::NotContains, &"ja".into()));
+        assert!(compare_value(&font, BinaryOp::Contains, &"ko".into()));
+        assert!(compare_value(&font, BinaryOp::Contains, &"en-US".into()));
+        assert!(compare_value(
+            &font,
+            BinaryOp::Eq,
+            &Value::LangSet("KO-kr|EN".into())
+        ));
+        assert!(!compare_value(&font, BinaryOp::Eq, &"en".into()));
+    }
+}
@@ -15,26 +15,50 @@
 #[macro_use]
 mod util;
 
+mod diagnostic;
 mod error;
+mod eval;
+mod matching;
 mod parser;
+mod splice;
+mod strict;
+mod subst;
 mod types;
+mod writer;
 
 pub type Result<T> = core::result::Result<T, Error>;
 
-pub use crate::error::Error;
+pub use crate::diagnostic::{Diagnostic, Diagnostics, Severity};
+pub use crate::error::{Error, FormatError};
+pub use crate::matching::FontSort;
+pub use crate::parser::ParseOptions;
+pub use crate::splice::{ElementNode, PartEditor, PartNode};
 pub use crate::types::*;
+pub use crate::writer::{format_config, write_config};
 
 /// Parse as raw config parts use this when you want custom handling config file
 ///
+/// Parts have [`Location`] without file, and invalid `<patelt>`s are dropped silently.
+///
 /// Otherwise, you may want [`FontConfig::merge_config`]
 pub fn parse_config_parts(s: &str) -> Result<Vec<ConfigPart>> {
-    crate::parser::parse_config(&roxmltree::Document::parse_with_options(
-        s,
-        roxmltree::ParsingOptions {
-            allow_dtd: true,
-            ..Default::default()
-        },
-    )?)?
+    parse_config_parts_with_options(s, &ParseOptions::default())
+}
+
+/// Same as [`parse_config_parts`] with `options`
+pub fn parse_config_parts_with_options(s: &str, options: &ParseOptions) -> Result<Vec<ConfigPart>> {
+    crate::parser::parse_config(
+        &roxmltree::Document::parse_with_options(
+            s,
+            roxmltree::ParsingOptions {
+                allow_dtd: true,
+                ..Default::default()
+            },
+        )?,
+        None,
+        options,
+        &mut Diagnostics::new(),
+    )?
     .collect()
 }
 
@@ -0,0 +1,683 @@
+//! Select fonts for a pattern
+//!
+//! This follows `FcFontMatch` of freedesktop.org's Fontconfig library.
+
+use crate::util::{eq_ignore_blanks_and_case, glob_match};
+use crate::*;
+
+use std::cmp::Ordering;
+
+/// Matching priority of properties, earlier is more important
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Priority {
+    File,
+    FontFormat,
+    Scalable,
+    Color,
+    Foundry,
+    Charset,
+    FamilyStrong,
+    PostscriptNameStrong,
+    Lang,
+    FamilyWeak,
+    PostscriptNameWeak,
+    Spacing,
+    Size,
+    PixelSize,
+    Style,
+    Slant,
+    Weight,
+    Width,
+    FontHasHint,
+    Decorative,
+    Antialias,
+    Rasterizer,
+    Outline,
+    Order,
+    FontVersion,
+}
+
+const PRIORITY_COUNT: usize = Priority::FontVersion as usize + 1;
+
+/// Score of a font for a pattern, lower is better
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Score([Double; PRIORITY_COUNT]);
+
+impl Score {
+    pub(crate) fn cmp(&self, other: &Self) -> Ordering {
+        self.0
+            .iter()
+            .zip(other.0.iter())
+            .map(|(l, r)| l.partial_cmp(r).unwrap_or(Ordering::Equal))
+            .find(|o| *o != Ordering::Equal)
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+/// Distance of pattern value and font value, `None` when types mismatch
+type CompareFn = fn(&Value, &Value) -> Option<Double>;
+
+struct Matcher {
+    compare: CompareFn,
+    strong: Priority,
+    weak: Priority,
+}
+
+impl Matcher {
+    fn new(compare: CompareFn, priority: Priority) -> Self {
+        Self {
+            compare,
+            strong: priority,
+            weak: priority,
+        }
+    }
+}
+
+fn matcher(kind: &PropertyKind) -> Option<Matcher> {
+    use PropertyKind as K;
+
+    Some(match kind {
+        K::File => Matcher::new(compare_filename, Priority::File),
+        K::Fontformat => Matcher::new(compare_string, Priority::FontFormat),
+        K::Scalable => Matcher::new(compare_bool, Priority::Scalable),
+        K::Color => Matcher::new(compare_bool, Priority::Color),
+        K::Foundry => Matcher::new(compare_string, Priority::Foundry),
+        K::Charset => Matcher::new(compare_charset, Priority::Charset),
+        K::Family => Matcher {
+            compare: compare_family,
+            strong: Priority::FamilyStrong,
+            weak: Priority::FamilyWeak,
+        },
+        K::Postscriptname => Matcher {
+            compare: compare_postscript,
+            strong: Priority::PostscriptNameStrong,
+            weak: Priority::PostscriptNameWeak,
+        },
+        K::Lang => Matcher::new(compare_lang, Priority::Lang),
+        K::Spacing => Matcher::new(compare_number, Priority::Spacing),
+        K::Size => Matcher::new(compare_range, Priority::Size),
+        K::PixelSize => Matcher::new(compare_range, Priority::PixelSize),
+        K::Style => Matcher::new(compare_string, Priority::Style),
+        K::Slant => Matcher::new(compare_number, Priority::Slant),
+        K::Weight => Matcher::new(compare_range, Priority::Weight),
+        K::Width => Matcher::new(compare_range, Priority::Width),
+        K::Fonthashint => Matcher::new(compare_bool, Priority::FontHasHint),
+        K::Decorative => Matcher::new(compare_bool, Priority::Decorative),
+        K::Antialias => Matcher::new(compare_bool, Priority::Antialias),
+        K::Rasterizer => Matcher::new(compare_string, Priority::Rasterizer),
+        K::Outline => Matcher::new(compare_bool, Priority::Outline),
+        K::Order => Matcher::new(compare_number, Priority::Order),
+        K::Fontversion => Matcher::new(compare_number, Priority::FontVersion),
+        _ => return None,
+    })
+}
+
+fn compare_number(v1: &Value, v2: &Value) -> Option<Double> {
+    Some((v1.as_double()? - v2.as_double()?).abs())
+}
+
+fn compare_string(v1: &Value, v2: &Value) -> Option<Double> {
+    match (v1, v2) {
+        (Value::String(s1), Value::String(s2)) => {
+            Some(!s1.eq_ignore_ascii_case(s2) as u8 as Double)
+        }
+        _ => None,
+    }
+}
+
+fn compare_bool(v1: &Value, v2: &Value) -> Option<Double> {
+    match (v1, v2) {
+        (Value::Bool(b1), Value::Bool(b2)) => Some((b1 != b2) as u8 as Double),
+        _ => None,
+    }
+}
+
+fn compare_filename(v1: &Value, v2: &Value) -> Option<Double> {
+    match (v1, v2) {
+        (Value::String(s1), Value::String(s2)) => Some(if s1 == s2 {
+            0.0
+        } else if s1.eq_ignore_ascii_case(s2) {
+            1.0
+        } else if glob_match(s1, s2) {
+            2.0
+        } else {
+            3.0
+        }),
+        _ => None,
+    }
+}
+
+fn compare_family(v1: &Value, v2: &Value) -> Option<Double> {
+    match (v1, v2) {
+        (Value::String(s1), Value::String(s2)) => {
+            Some(!eq_ignore_blanks_and_case(s1, s2) as u8 as Double)
+        }
+        _ => None,
+    }
+}
+
+fn compare_postscript(v1: &Value, v2: &Value) -> Option<Double> {
+    match (v1, v2) {
+        (Value::String(s1), Value::String(s2)) => {
+            if eq_ignore_blanks_and_case(s1, s2) {
+                return Some(0.0);
+            }
+
+            // ratio of `s1` not matched by common prefix, ignoring case and delimiters
+            let is_delim = |c: &char| *c == ' ' || *c == '-';
+            let mut c2 = s2.chars().filter(|c| !is_delim(c));
+            let matched = s1
+                .chars()
+                .filter(|c| !is_delim(c))
+                .take_while(|c1| matches!(c2.next(), Some(c2) if c1.eq_ignore_ascii_case(&c2)))
+                .count();
+            let len = s1.chars().filter(|c| !is_delim(c)).count().max(1);
+
+            Some((len - matched) as Double / len as Double)
+        }
+        _ => None,
+    }
+}
+
+fn compare_lang(v1: &Value, v2: &Value) -> Option<Double> {
+    v1.compare_lang(v2).map(|result| match result {
+        LangResult::Equal => 0.0,
+        LangResult::DifferentTerritory => 1.0,
+        LangResult::DifferentLang => 2.0,
+    })
+}
+
+fn compare_charset(v1: &Value, v2: &Value) -> Option<Double> {
+    match (v1, v2) {
+        (Value::CharSet(c1), Value::CharSet(c2)) => Some(c1.subtract(c2).count() as Double),
+        _ => None,
+    }
+}
+
+/// Distance between two ranges, numbers are treated as single point ranges
+fn compare_range(v1: &Value, v2: &Value) -> Option<Double> {
+    let (b1, e1) = v1.as_range()?;
+    let (b2, e2) = v2.as_range()?;
+
+    if e1 < b2 || e2 < b1 {
+        Some((b2 - e1).abs().min((b1 - e2).abs()))
+    } else {
+        Some(0.0)
+    }
+}
+
+impl Pattern {
+    /// Score `font` for this pattern, `None` when some property has mismatched types
+    ///
+    /// This follows `FcCompare`
+    pub(crate) fn score(&self, font: &Pattern) -> Option<Score> {
+        let mut score = [0.0; PRIORITY_COUNT];
+
+        for (kind, values) in self.iter() {
+            let matcher = match matcher(kind) {
+                Some(matcher) => matcher,
+                None => continue,
+            };
+            let font_values = match font.get(kind) {
+                Some(values) => values,
+                None => continue,
+            };
+
+            let mut best: Double = 1e99;
+            let mut best_strong: Double = 1e99;
+            let mut best_weak: Double = 1e99;
+
+            // earlier pattern values are preferred
+            for (i, (value, binding)) in values.iter().enumerate() {
+                for (font_value, _) in font_values.iter() {
+                    let v = (matcher.compare)(value, font_value)? * 1000.0 + i as Double;
+
+                    best = best.min(v);
+                    if *binding == EditBinding::Strong {
+                        best_strong = best_strong.min(v);
+                    } else {
+                        best_weak = best_weak.min(v);
+                    }
+                }
+            }
+
+            if matcher.strong == matcher.weak {
+                score[matcher.strong as usize] += best;
+            } else {
+                score[matcher.strong as usize] += best_strong;
+                score[matcher.weak as usize] += best_weak;
+            }
+        }
+
+        Some(Score(score))
+    }
+
+    /// Find the font best matching this pattern
+    ///
+    /// This is equivalent to `FcFontMatch` without `FcFontRenderPrepare`,
+    /// pattern should be prepared by [`FontConfig::substitute`] and [`Pattern::default_substitute`].
+    /// Fonts whose properties have types mismatching the pattern are skipped.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use fontconfig_parser::*;
+    ///
+    /// let mut config = FontConfig::default();
+    /// config.merge_config("/etc/fonts/fonts.conf").unwrap();
+    ///
+    /// let fonts: Vec<Pattern> = Vec::new(); // patterns of installed fonts
+    ///
+    /// let mut pat: Pattern = vec![(PropertyKind::Family, "monospace".into())]
+    ///     .into_iter()
+    ///     .collect();
+    /// config.substitute(&mut pat);
+    /// pat.default_substitute();
+    ///
+    /// let font = pat.font_match(&fonts);
+    /// ```
+    pub fn font_match<'a>(
+        &self,
+        fonts: impl IntoIterator<Item = &'a Pattern>,
+    ) -> Option<&'a Pattern> {
+        let mut best: Option<(Score, &Pattern)> = None;
+
+        for font in fonts {
+            let score = match self.score(font) {
+                Some(score) => score,
+                None => continue,
+            };
+
+            match best {
+                Some((ref best_score, _)) if score.cmp(best_score) != Ordering::Less => {}
+                _ => best = Some((score, font)),
+            }
+        }
+
+        best.map(|(_, font)| font)
+    }
+
+    /// Sort fonts by how well they match this pattern
+    ///
+    /// This is equivalent to `FcFontSort`, pattern should be prepared like [`Pattern::font_match`].
+    /// When `trim` is set, fonts which don't add new codepoints to the coverage of previous fonts are removed.
+    pub fn font_sort<'a>(
+        &self,
+        fonts: impl IntoIterator<Item = &'a Pattern>,
+        trim: bool,
+    ) -> FontSort<'a> {
+        let mut nodes: Vec<(Score, &Pattern)> = fonts
+            .into_iter()
+            .filter_map(|font| Some((self.score(font)?, font)))
+            .collect();
+
+        // stable sort keeps the order of fonts with same score
+        nodes.sort_by(|(l, _), (r, _)| l.cmp(r));
+
+        // each requested language only prefers the best font covering it,
+        // later fonts covering no other language lose their lang score
+        let langs = self.get(&PropertyKind::Lang).unwrap_or_default();
+        let mut satisfied = vec![false; langs.len()];
+        for (score, font) in nodes.iter_mut() {
+            let lang_score = &mut score.0[Priority::Lang as usize];
+            let mut satisfies = false;
+
+            if *lang_score < 2000.0 {
+                if let Some(font_lang) = font.get_value(&PropertyKind::Lang) {
+                    let pos = langs
+                        .iter()
+                        .zip(satisfied.iter())
+                        .position(|((lang, _), sat)| {
+                            !sat && matches!(compare_lang(lang, font_lang), Some(v) if v < 2.0)
+                        });
+                    if let Some(pos) = pos {
+                        satisfied[pos] = true;
+                        satisfies = true;
+                    }
+                }
+            }
+
+            if !satisfies {
+                *lang_score = 10000.0;
+            }
+        }
+        nodes.sort_by(|(l, _), (r, _)| l.cmp(r));
+
+        let mut sort = FontSort {
+            fonts: Vec::with_capacity(nodes.len()),
+            charset: CharSet::new(),
+        };
+
+        for (_, font) in nodes {
+            let adds = match font.get_value(&PropertyKind::Charset) {
+                Some(Value::CharSet(charset))
+                    if !trim || sort.charset.is_empty() || !charset.is_subset(&sort.charset) =>
+                {
+                    sort.charset = sort.charset.union(charset);
+                    true
+                }
+                _ => false,
+            };
+
+            if !trim || adds {
+                sort.fonts.push(font);
+            }
+        }
+
+        sort
+    }
+}
+
+impl FontConfig {
+    /// Combine `pattern` and matched `font` then apply every `<match target="font">` rule
+    ///
+    /// This is equivalent to `FcFontRenderPrepare`. Properties in both patterns take the font value
+    /// closest to the pattern, other properties are copied from either pattern.
+    /// Family, style and full name keep every font value, the one in the language closest to
+    /// the pattern's `*lang` comes first, or the one closest to the pattern when the font has no languages.
+    pub fn render_prepare(&self, pattern: &Pattern, font: &Pattern) -> Pattern {
+        let mut new = Pattern::new();
+
+        for (kind, font_values) in font.iter() {
+            if is_name_lang(kind) {
+                // set with the names
+                continue;
+            }
+
+            if let Some(lang_kind) = name_lang(kind) {
+                match font.get(&lang_kind) {
+                    Some(font_langs) => {
+                        let best = pattern
+                            .get(&lang_kind)
+                            .and_then(|langs| best_index(compare_lang, langs, font_langs));
+                        let mut langs = font_langs.to_vec();
+                        let mut names = font_values.to_vec();
+
+                        if let Some((_, n)) = best {
+                            langs[n].1 = EditBinding::Strong;
+                            move_to_front(&mut langs, n);
+                            move_to_front(&mut names, n);
+                        }
+
+                        new.replace(kind.clone(), names);
+                        new.replace(lang_kind, langs);
+                    }
+                    None => {
+                        let best = match (matcher(kind), pattern.get(kind)) {
+                            (Some(matcher), Some(values)) => {
+                                best_index(matcher.compare, values, font_values)
+                            }
+                            _ => None,
+                        };
+                        let mut names = font_values.to_vec();
+
+                        if let Some((_, n)) = best {
+                            move_to_front(&mut names, n);
+                        }
+
+                        new.replace(kind.clone(), names);
+                    }
+                }
+                continue;
+            }
+
+            let best = match (matcher(kind), pattern.get(kind)) {
+                (Some(matcher), Some(values)) => best_value(&matcher, values, font_values),
+                _ => None,
+            };
+
+            match best {
+                Some(value) => new.add(kind.clone(), value, EditBinding::Strong),
+                None => {
+                    new.replace(kind.clone(), font_values.iter().cloned());
+                }
+            }
+        }
+
+        for (kind, values) in pattern.iter() {
+            if font.get(kind).is_none() && !is_name_lang(kind) {
+                new.replace(kind.clone(), values.iter().cloned());
+            }
+        }
+
+        for m in self.match_rules() {
+            m.substitute(MatchTarget::Font, &mut new, Some(pattern));
+        }
+
+        new
+    }
+
+    /// Effective rendering options of `font` matched for `pattern`
+    ///
+    /// See [`FontConfig::render_prepare`]
+    pub fn render_settings(&self, pattern: &Pattern, font: &Pattern) -> RenderSettings {
+        RenderSettings::from(&self.render_prepare(pattern, font))
+    }
+}
+
+/// Languages of `kind` when it is a name property
+fn name_lang(kind: &PropertyKind) -> Option<PropertyKind> {
+    match kind {
+        PropertyKind::Family => Some(PropertyKind::FamilyLang),
+        PropertyKind::Style => Some(PropertyKind::StyleLang),
+        PropertyKind::FullName => Some(PropertyKind::FullNameLang),
+        _ => None,
+    }
+}
+
+fn is_name_lang(kind: &PropertyKind) -> bool {
+    matches!(
+        kind,
+        PropertyKind::FamilyLang | PropertyKind::StyleLang | PropertyKind::FullNameLang
+    )
+}
+
+/// Move the value at `n` to the front, does nothing when it is out of bounds
+fn move_to_front(values: &mut [(Value, EditBinding)], n: usize) {
+    if n < values.len() {
+        values[..=n].rotate_right(1);
+    }
+}
+
+/// Indices of the pattern value and the font value closest to each other
+///
+/// Earlier pattern values are preferred, then earlier font values.
+fn best_index(
+    compare: CompareFn,
+    values: &[(Value, EditBinding)],
+    font_values: &[(Value, EditBinding)],
+) -> Option<(usize, usize)> {
+    let mut best: Option<(Double, usize, usize)> = None;
+
+    for (i, (value, _)) in values.iter().enumerate() {
+        for (j, (font_value, _)) in font_values.iter().enumerate() {
+            let v = compare(value, font_value)? * 1000.0 + i as Double;
+            match best {
+                Some((best, _, _)) if best <= v => {}
+                _ => best = Some((v, i, j)),
+            }
+        }
+    }
+
+    best.map(|(_, i, j)| (i, j))
+}
+
+/// Font value closest to pattern values, ranges are narrowed to a point
+fn best_value(
+    matcher: &Matcher,
+    values: &[(Value, EditBinding)],
+    font_values: &[(Value, EditBinding)],
+) -> Option<Value> {
+    let (i, j) = best_index(matcher.compare, values, font_values)?;
+    let (value, font_value) = (&values[i].0, &font_values[j].0);
+
+    match (value.as_range(), font_value) {
+        (Some((b1, e1)), Value::Range(..)) => {
+            let (b2, e2) = font_value.as_range()?;
+            let d = if e1 < b2 {
+                b2
+            } else if e2 < b1 {
+                e2
+            } else {
+                (b1.max(b2) + e1.min(e2)) * 0.5
+            };
+            Some(Value::Double(d))
+        }
+        _ => Some(font_value.clone()),
+    }
+}
+
+/// Result of [`Pattern::font_sort`]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FontSort<'a> {
+    /// Fonts in priority order
+    pub fonts: Vec<&'a Pattern>,
+    /// Union of the charsets of [`fonts`](Self::fonts)
+    pub charset: CharSet,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn font(family: &str, weight: Int, slant: Int) -> Pattern {
+        vec![
+            (PropertyKind::Family, Value::from(family)),
+            (PropertyKind::Weight, Value::Int(weight)),
+            (PropertyKind::Slant, Value::Int(slant)),
+        ]
+        .into_iter()
+        .collect()
+    }
+
+    #[test]
+    fn sort_trim_coverage() {
+        let mut fonts = vec![
+            font("Latin", 80, 0),
+            font("Latin Bold", 200, 0),
+            font("Hangul", 80, 0),
+        ];
+        let charsets = [
+            CharSet::from_ranges([(0x20, 0x7e)]),
+            CharSet::from_ranges([(0x41, 0x5a)]),
+            CharSet::from_ranges([(0x20, 0x7e), (0xac00, 0xd7a3)]),
+        ];
+        for (font, charset) in fonts.iter_mut().zip(charsets.iter()) {
+            font.add(
+                PropertyKind::Charset,
+                Value::CharSet(charset.clone()),
+                EditBinding::Strong,
+            );
+        }
+
+        let mut pat = Pattern::new();
+        pat.add(PropertyKind::Family, "Latin".into(), EditBinding::Strong);
+        pat.default_substitute();
+
+        let sort = pat.font_sort(&fonts, false);
+        k9::assert_equal!(sort.fonts, vec![&fonts[0], &fonts[2], &fonts[1]]);
+
+        let sort = pat.font_sort(&fonts, true);
+        k9::assert_equal!(sort.fonts, vec![&fonts[0], &fonts[2]]);
+        k9::assert_equal!(sort.charset.ranges(), &[(0x20, 0x7e), (0xac00, 0xd7a3)][..]);
+    }
+
+    #[test]
+    fn sort_lang_satisfied() {
+        let mut fonts = vec![
+            font("Japanese A", 80, 0),
+            font("Japanese B", 80, 0),
+            font("Korean", 80, 0),
+        ];
+        for (font, lang) in fonts.iter_mut().zip(["ja", "ja", "ko"].iter()) {
+            font.add(
+                PropertyKind::Lang,
+                Value::LangSet((*lang).into()),
+                EditBinding::Strong,
+            );
+        }
+
+        let mut pat = Pattern::new();
+        pat.add(PropertyKind::Lang, "ja".into(), EditBinding::Strong);
+        pat.add(PropertyKind::Lang, "ko".into(), EditBinding::Strong);
+        pat.default_substitute();
+
+        // "ja" is covered by the first font, so the second one doesn't get the lang bonus
+        let sort = pat.font_sort(&fonts, false);
+        k9::assert_equal!(sort.fonts, vec![&fonts[0], &fonts[2], &fonts[1]]);
+    }
+
+    #[test]
+    fn render_prepare_names() {
+        let font: Pattern = vec![
+            (PropertyKind::Family, Value::from("Source Han Sans")),
+            (PropertyKind::Family, Value::from("源ノ角ゴシック")),
+            (PropertyKind::FamilyLang, Value::from("en")),
+            (PropertyKind::FamilyLang, Value::from("ja")),
+            (PropertyKind::Style, Value::from("Regular")),
+            (PropertyKind::Style, Value::from("Book")),
+            (PropertyKind::Style, Value::from("Normal")),
+        ]
+        .into_iter()
+        .collect();
+
+        let mut pat = Pattern::new();
+        pat.add(PropertyKind::FamilyLang, "ja".into(), EditBinding::Strong);
+        pat.add(PropertyKind::Style, "Book".into(), EditBinding::Strong);
+
+        let new = FontConfig::default().render_prepare(&pat, &font);
+
+        // the name in the pattern language comes first
+        k9::assert_equal!(
+            new.get(&PropertyKind::Family).unwrap(),
+            &[
+                (Value::from("源ノ角ゴシック"), EditBinding::Strong),
+                (Value::from("Source Han Sans"), EditBinding::Strong),
+            ][..]
+        );
+        k9::assert_equal!(
+            new.get(&PropertyKind::FamilyLang).unwrap(),
+            &[
+                (Value::from("ja"), EditBinding::Strong),
+                (Value::from("en"), EditBinding::Strong),
+            ][..]
+        );
+        // without languages, the name matching the pattern comes first
+        k9::assert_equal!(
+            new.get(&PropertyKind::Style).unwrap(),
+            &[
+                (Value::from("Book"), EditBinding::Strong),
+                (Value::from("Regular"), EditBinding::Strong),
+                (Value::from("Normal"), EditBinding::Strong),
+            ][..]
+        );
+    }
+
+    #[test]
+    fn match_family_and_style() {
+        let fonts = vec![
+            font("DejaVu Sans", 80, 0),
+            font("DejaVu Sans", 200, 0),
+            font("Noto Sans", 200, 100),
+            font("Noto Sans", 200, 0),
+        ];
+
+        let mut pat = Pattern::new();
+        pat.add(PropertyKind::Family, "notosans".into(), EditBinding::Strong);
+        pat.add(
+            PropertyKind::Family,
+            "DejaVu Sans".into(),
+            EditBinding::Weak,
+        );
+        pat.add(PropertyKind::Weight, Value::Int(200), EditBinding::Strong);
+        pat.default_substitute();
+
+        k9::assert_equal!(pat.font_match(&fonts), Some(&fonts[3]));
+
+        pat.replace(
+            PropertyKind::Family,
+            vec![(Value::from("Unknown"), EditBinding::Strong)],
+        );
+        k9::assert_equal!(pat.font_match(&fonts), Some(&fonts[1]));
+    }
+}
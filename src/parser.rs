@@ -1,64 +1,113 @@
-#![allow(clippy::useless_format)]
-
+use crate::util::parse_int;
 use crate::*;
 use roxmltree::Node;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Options for parsing config
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParseOptions {
+    /// Reject unknown elements, unknown attributes and unexpected text content
+    ///
+    /// Otherwise, they are ignored like fontconfig does.
+    /// Document is validated against the structure of fontconfig's `fonts.dtd`.
+    pub strict: bool,
+}
 
 pub fn parse_config<'a>(
     xml_doc: &'a roxmltree::Document,
+    file: Option<Arc<Path>>,
+    options: &ParseOptions,
+    diagnostics: &'a mut Diagnostics,
 ) -> Result<impl Iterator<Item = Result<ConfigPart>> + 'a> {
     let fontconfig = xml_doc.root_element();
 
     if fontconfig.tag_name().name() != "fontconfig" {
-        return Err(Error::NoFontconfig);
+        return Err(Error::NoFontconfig.at(fontconfig).with_file(file.as_ref()));
     }
 
-    Ok(fontconfig
-        .children()
-        .filter_map(|c| parse_config_part(c).transpose()))
+    if options.strict {
+        crate::strict::validate(fontconfig).map_err(|e| e.with_file(file.as_ref()))?;
+    }
+
+    Ok(fontconfig.children().filter_map(move |c| {
+        located(c, || parse_config_part(c, file.as_ref(), diagnostics))
+            .map_err(|e| e.with_file(file.as_ref()))
+            .transpose()
+    }))
 }
 
-fn parse_config_part(child: Node) -> Result<Option<ConfigPart>> {
+/// Attach location of `node` to the error of `f` if it doesn't have one
+fn located<T>(node: Node, f: impl FnOnce() -> Result<T>) -> Result<T> {
+    f().map_err(|e| e.at(node))
+}
+
+fn parse_config_part(
+    child: Node,
+    file: Option<&Arc<Path>>,
+    diagnostics: &mut Diagnostics,
+) -> Result<Option<ConfigPart>> {
+    let location = |node| Location::from_node(node, file);
+
+    if !child.is_element() {
+        return Ok(None);
+    }
+
     let part = match child.tag_name().name() {
-        "description" => ConfigPart::Description(try_text!(child).into()),
+        "description" => ConfigPart::Description(Description {
+            text: try_text!(child).into(),
+            unknown_attributes: parse_attributes(child),
+        }),
         "alias" => {
-            let mut alias = Alias::default();
+            let mut alias = Alias {
+                location: location(child),
+                ..Default::default()
+            };
 
-            for child in child.children() {
-                let families =
-                    child
-                        .children()
-                        .filter_map(|family| match family.tag_name().name() {
-                            "family" => family.text().map(Into::into),
-                            _ => None,
-                        });
+            parse_attrs!(child, {
+                "binding" => alias.binding,
+            }, |attr| push_unknown_attribute(&mut alias.unknown_attributes, child, attr));
 
-                match child.tag_name().name() {
+            for child in child.children() {
+                let (families, unknown) = match child.tag_name().name() {
                     "family" => {
                         alias.alias = try_text!(child).into();
+                        continue;
                     }
-                    "prefer" => {
-                        alias.prefer.extend(families);
-                    }
-                    "accept" => {
-                        alias.accept.extend(families);
+                    "prefer" => (&mut alias.prefer, &mut alias.prefer_unknown),
+                    "accept" => (&mut alias.accept, &mut alias.accept_unknown),
+                    "default" => (&mut alias.default, &mut alias.default_unknown),
+                    _ if child.is_element() => {
+                        alias.unknown.push(parse_unknown(child, file));
+                        continue;
                     }
-                    "default" => {
-                        alias.default.extend(families);
+                    _ => continue,
+                };
+
+                for family in child.children().filter(|c| c.is_element()) {
+                    match family.text() {
+                        Some(text) if family.tag_name().name() == "family" => {
+                            families.push(text.into())
+                        }
+                        _ => unknown.push(parse_unknown(family, file)),
                     }
-                    _ => {}
                 }
             }
 
             ConfigPart::Alias(alias)
         }
         "dir" => {
-            let mut dir = Dir::default();
+            let mut dir = Dir {
+                location: location(child),
+                ..Default::default()
+            };
 
             parse_attrs!(child, {
                 "prefix" => dir.prefix,
             }, {
                 "salt" => dir.salt,
-            });
+            }, |attr| push_unknown_attribute(&mut dir.unknown_attributes, child, attr));
 
             dir.path = try_text!(child).into();
 
@@ -66,39 +115,48 @@ fn parse_config_part(child: Node) -> Result<Option<ConfigPart>> {
         }
         "reset-dirs" => ConfigPart::ResetDirs,
         "remap-dir" => {
-            let mut dir = RemapDir::default();
+            let mut dir = RemapDir {
+                location: location(child),
+                ..Default::default()
+            };
 
             parse_attrs!(child, {
                 "prefix" => dir.prefix,
             }, {
                 "salt" => dir.salt,
                 "as-path" => dir.as_path,
-            });
+            }, |attr| push_unknown_attribute(&mut dir.unknown_attributes, child, attr));
 
             dir.path = try_text!(child).into();
 
             ConfigPart::RemapDir(dir)
         }
         "cachedir" => {
-            let mut dir = CacheDir::default();
+            let mut dir = CacheDir {
+                location: location(child),
+                ..Default::default()
+            };
 
             parse_attrs!(child, {
                 "prefix" => dir.prefix,
-            });
+            }, |attr| push_unknown_attribute(&mut dir.unknown_attributes, child, attr));
 
             dir.path = try_text!(child).into();
 
             ConfigPart::CacheDir(dir)
         }
         "include" => {
-            let mut dir = Include::default();
+            let mut dir = Include {
+                location: location(child),
+                ..Default::default()
+            };
             let mut ignore_missing = "";
 
             parse_attrs!(child, {
                 "prefix" => dir.prefix,
             }, {
                 "ignore_missing" => ignore_missing,
-            });
+            }, |attr| push_unknown_attribute(&mut dir.unknown_attributes, child, attr));
 
             dir.ignore_missing = matches!(ignore_missing, "yes");
             dir.path = try_text!(child).into();
@@ -106,22 +164,38 @@ fn parse_config_part(child: Node) -> Result<Option<ConfigPart>> {
             ConfigPart::Include(dir)
         }
         "config" => {
-            let mut config = Config::default();
+            let mut config = Config {
+                location: location(child),
+                ..Default::default()
+            };
 
             for child in child.children() {
                 match child.tag_name().name() {
                     "rescan" => {
-                        if let Some(int) = child.first_element_child() {
-                            if int.tag_name().name() == "int" {
-                                config.rescans.push(try_text!(int).parse()?);
+                        let mut children = child.children().filter(|c| c.is_element());
+
+                        match children.next() {
+                            Some(int) if int.tag_name().name() == "int" => {
+                                config
+                                    .rescans
+                                    .push(located(int, || parse_int(try_text!(int)))?);
                             }
+                            Some(other) => config.rescan_unknown.push(parse_unknown(other, file)),
+                            None => {}
                         }
+                        config
+                            .rescan_unknown
+                            .extend(children.map(|c| parse_unknown(c, file)));
                     }
                     "blank" => {
-                        if let Some(child) = child.first_element_child() {
-                            config.blanks.push(parse_int_or_range(child)?);
+                        for c in child.children().filter(|c| c.is_element()) {
+                            match c.tag_name().name() {
+                                "int" | "range" => config.blanks.push(parse_int_or_range(c)?),
+                                _ => config.blank_unknown.push(parse_unknown(c, file)),
+                            }
                         }
                     }
+                    _ if child.is_element() => config.unknown.push(parse_unknown(child, file)),
                     _ => {}
                 }
             }
@@ -129,28 +203,20 @@ fn parse_config_part(child: Node) -> Result<Option<ConfigPart>> {
             ConfigPart::Config(config)
         }
         "selectfont" => {
-            let mut s = SelectFont::default();
+            let mut s = SelectFont {
+                location: location(child),
+                ..Default::default()
+            };
 
             for child in child.children() {
-                let matches = child.children().filter_map(|c| match c.tag_name().name() {
-                    "pattern" => {
-                        let patelts = c.children().filter_map(|patelt| {
-                            if patelt.tag_name().name() == "patelt" {
-                                let mut kind = PropertyKind::default();
-                                parse_attrs_opt!(patelt, {
-                                    "name" => kind,
-                                });
-                                parse_expr(patelt.first_element_child()?)
-                                    .ok()
-                                    .map(|expr| kind.make_property(expr))
-                            } else {
-                                None
-                            }
-                        });
-                        Some(FontMatch::Pattern(patelts.collect()))
+                let matches = child.children().filter(|c| c.is_element()).map(|c| {
+                    match (c.tag_name().name(), c.text()) {
+                        ("pattern", _) => {
+                            FontMatch::Pattern(parse_pattern(c, file, &mut *diagnostics))
+                        }
+                        ("glob", Some(glob)) => FontMatch::Glob(glob.into()),
+                        _ => FontMatch::Unknown(parse_unknown(c, file)),
                     }
-                    "glob" => c.text().map(Into::into).map(FontMatch::Glob),
-                    _ => None,
                 });
 
                 match child.tag_name().name() {
@@ -160,6 +226,7 @@ fn parse_config_part(child: Node) -> Result<Option<ConfigPart>> {
                     "rejectfont" => {
                         s.rejects.extend(matches);
                     }
+                    _ if child.is_element() => s.unknown.push(parse_unknown(child, file)),
                     _ => {}
                 }
             }
@@ -167,146 +234,398 @@ fn parse_config_part(child: Node) -> Result<Option<ConfigPart>> {
             ConfigPart::SelectFont(s)
         }
         "match" => {
-            let mut m = Match::default();
+            let mut m = Match {
+                location: location(child),
+                ..Default::default()
+            };
 
             parse_attrs!(child, {
                 "target" => m.target,
-            });
+            }, |attr| push_unknown_attribute(&mut m.unknown_attributes, child, attr));
 
             for child in child.children() {
                 match child.tag_name().name() {
-                    "test" => {
-                        let mut t = Test::default();
-                        let mut kind = PropertyKind::default();
-
-                        parse_attrs!(child, {
-                            "name" => kind,
-                            "qual" => t.qual,
-                            "target" => t.target,
-                            "compare" => t.compare,
-                        });
-
-                        t.value = kind.make_property(parse_expr(
-                            child
-                                .first_element_child()
-                                .ok_or_else(|| Error::InvalidFormat(format!("Empty test value")))?,
-                        )?);
-
-                        m.tests.push(t);
-                    }
+                    "test" => m.tests.push(parse_test(child, file)?),
+                    "edit" => m.edits.push(parse_edit(child, file)?),
+                    _ if child.is_element() => m.unknown.push(parse_unknown(child, file)),
+                    _ => {}
+                }
+            }
 
-                    "edit" => {
-                        let mut e = Edit::default();
-                        let mut kind = PropertyKind::default();
+            ConfigPart::Match(m)
+        }
+        _ => ConfigPart::Unknown(parse_unknown(child, file)),
+    };
 
-                        parse_attrs!(child, {
-                            "name" => kind,
-                            "mode" => e.mode,
-                            "binding" => e.binding,
-                        });
+    Ok(Some(part))
+}
 
-                        e.value = kind.make_property(parse_expr(
-                            child
-                                .first_element_child()
-                                .ok_or_else(|| Error::InvalidFormat(format!("Empty edit value")))?,
-                        )?);
+fn parse_attributes(node: Node) -> Vec<(String, String)> {
+    let mut attrs = Vec::new();
+    for attr in node.attributes() {
+        push_unknown_attribute(&mut attrs, node, attr);
+    }
+    attrs
+}
 
-                        m.edits.push(e);
-                    }
-                    _ => {}
+/// Name with the prefix of `namespace`, and the declaration of the prefix if it needs one
+fn qualified_name(
+    node: Node,
+    namespace: Option<&str>,
+    name: &str,
+) -> (String, Option<(String, String)>) {
+    let prefix = namespace.and_then(|uri| {
+        node.namespaces()
+            .find(|ns| ns.uri() == uri && ns.name().is_some())
+            .and_then(|ns| ns.name())
+    });
+
+    match (prefix, namespace) {
+        // `xml` prefix is always declared
+        (Some("xml"), _) => (format!("xml:{}", name), None),
+        (Some(prefix), Some(uri)) => (
+            format!("{}:{}", prefix, name),
+            Some((format!("xmlns:{}", prefix), uri.into())),
+        ),
+        _ => (name.into(), None),
+    }
+}
+
+/// Push `attr` of `node` which is not modeled by this crate
+///
+/// Prefix of namespaced attribute is kept, and its declaration is pushed after it
+/// unless `attrs` already has one, so the attribute is written back in the same namespace.
+fn push_unknown_attribute(
+    attrs: &mut Vec<(String, String)>,
+    node: Node,
+    attr: roxmltree::Attribute,
+) {
+    let (name, declaration) = qualified_name(node, attr.namespace(), attr.name());
+
+    attrs.push((name, attr.value().into()));
+    push_declaration(attrs, declaration);
+}
+
+fn push_declaration(attrs: &mut Vec<(String, String)>, declaration: Option<(String, String)>) {
+    if let Some(declaration) = declaration {
+        if !attrs.iter().any(|(k, _)| *k == declaration.0) {
+            attrs.push(declaration);
+        }
+    }
+}
+
+fn parse_unknown(node: Node, file: Option<&Arc<Path>>) -> UnknownElement {
+    let (name, declaration) =
+        qualified_name(node, node.tag_name().namespace(), node.tag_name().name());
+    let mut attributes = Vec::new();
+    push_declaration(&mut attributes, declaration);
+    for attr in node.attributes() {
+        push_unknown_attribute(&mut attributes, node, attr);
+    }
+
+    UnknownElement {
+        name,
+        attributes,
+        children: node
+            .children()
+            .filter_map(|child| {
+                if child.is_element() {
+                    Some(UnknownNode::Element(parse_unknown(child, file)))
+                } else if child.is_text() {
+                    child.text().map(|text| UnknownNode::Text(text.into()))
+                } else {
+                    None
                 }
+            })
+            .collect(),
+        location: Location::from_node(node, file),
+    }
+}
+
+/// Invalid `<patelt>`s are kept as unknown elements and reported to `diagnostics`
+fn parse_pattern(
+    node: Node,
+    file: Option<&Arc<Path>>,
+    diagnostics: &mut Diagnostics,
+) -> FontPattern {
+    let mut pattern = FontPattern::default();
+
+    for child in node.children().filter(|c| c.is_element()) {
+        if child.tag_name().name() != "patelt" {
+            pattern.unknown.push(parse_unknown(child, file));
+            continue;
+        }
+
+        match parse_patelt(child) {
+            Ok(patelt) => pattern.patelts.push(patelt),
+            Err(err) => {
+                diagnostics.push(
+                    Severity::Warning,
+                    Location::from_node(child, file),
+                    err.with_file(file),
+                );
+                pattern.unknown.push(parse_unknown(child, file));
             }
+        }
+    }
 
-            ConfigPart::Match(m)
+    pattern
+}
+
+fn parse_patelt(node: Node) -> Result<Patelt> {
+    located(node, || {
+        let mut kind = PropertyKind::default();
+        let mut unknown_attributes = Vec::new();
+
+        parse_attrs!(node, {
+            "name" => kind,
+        }, |attr| push_unknown_attribute(&mut unknown_attributes, node, attr));
+
+        let property = kind.make_property(parse_expr(
+            node.first_element_child()
+                .ok_or(Error::InvalidFormat(FormatError::MissingExpression))?,
+        )?);
+
+        Ok(Patelt {
+            property,
+            unknown_attributes,
+        })
+    })
+}
+
+fn parse_test(node: Node, file: Option<&Arc<Path>>) -> Result<Test> {
+    located(node, || {
+        let mut t = Test {
+            location: Location::from_node(node, file),
+            ..Default::default()
+        };
+        let mut kind = PropertyKind::default();
+
+        parse_attrs!(node, {
+            "name" => kind,
+            "qual" => t.qual,
+            "target" => t.target,
+            "compare" => t.compare,
+            "ignore-blanks" => t.ignore_blanks,
+        }, |attr| push_unknown_attribute(&mut t.unknown_attributes, node, attr));
+
+        let mut children = node.children().filter(|c| c.is_element());
+
+        t.value = kind.make_property(parse_expr(
+            children
+                .next()
+                .ok_or(Error::InvalidFormat(FormatError::EmptyTest))?,
+        )?);
+        for child in children {
+            if is_expr(child) {
+                t.extra_values.push(parse_expr(child)?);
+            } else {
+                t.unknown.push(parse_unknown(child, file));
+            }
         }
-        _ => {
-            return Ok(None);
+
+        Ok(t)
+    })
+}
+
+fn parse_edit(node: Node, file: Option<&Arc<Path>>) -> Result<Edit> {
+    located(node, || {
+        let mut e = Edit {
+            location: Location::from_node(node, file),
+            ..Default::default()
+        };
+        let mut kind = PropertyKind::default();
+
+        parse_attrs!(node, {
+            "name" => kind,
+            "mode" => e.mode,
+            "binding" => e.binding,
+        }, |attr| push_unknown_attribute(&mut e.unknown_attributes, node, attr));
+
+        let mut children = node.children().filter(|c| c.is_element());
+
+        e.value = kind.make_property(parse_expr(
+            children
+                .next()
+                .ok_or(Error::InvalidFormat(FormatError::EmptyEdit))?,
+        )?);
+        for child in children {
+            if is_expr(child) {
+                e.extra_values.push(parse_expr(child)?);
+            } else {
+                e.unknown.push(parse_unknown(child, file));
+            }
         }
-    };
 
-    Ok(Some(part))
+        Ok(e)
+    })
 }
 
 fn parse_int_or_range(node: Node) -> Result<IntOrRange> {
-    let mut texts = get_texts(&node);
+    located(node, || {
+        let mut texts = get_texts(&node);
+
+        match node.tag_name().name() {
+            "int" => Ok(IntOrRange::Int(parse_int(try_text!(node))?)),
+            "range" => Ok(IntOrRange::Range(
+                parse_int(try_next!(texts, FormatError::MissingInt))?,
+                parse_int(try_next!(texts, FormatError::MissingInt))?,
+            )),
+            name => Err(Error::InvalidFormat(FormatError::ExpectIntOrRange(
+                name.into(),
+            ))),
+        }
+    })
+}
 
+/// `<int>` or `<range>` in `<charset>`
+fn parse_code_points(node: Node) -> Result<IntOrRange> {
+    let value = parse_int_or_range(node)?;
+    let min = match value {
+        IntOrRange::Int(c) => c,
+        IntOrRange::Range(begin, end) => begin.min(end),
+    };
+
+    if min < 0 {
+        return Err(Error::InvalidFormat(FormatError::NegativeCodePoint(min)).at(node));
+    }
+
+    Ok(value)
+}
+
+/// Whether `node` is an element [`parse_expr`] knows
+fn is_expr(node: Node) -> bool {
     match node.tag_name().name() {
-        "int" => Ok(IntOrRange::Int(try_text!(node).parse()?)),
-        "range" => Ok(IntOrRange::Range(
-            try_next!(texts, "Expect int").parse()?,
-            try_next!(texts, "Expect int").parse()?,
-        )),
-        _ => Err(Error::InvalidFormat(format!("Expect IntOrRange"))),
+        "string" | "langset" | "double" | "int" | "bool" | "const" | "matrix" | "charset"
+        | "range" | "name" => true,
+        name => {
+            name.parse::<ListOp>().is_ok()
+                || name.parse::<UnaryOp>().is_ok()
+                || name.parse::<BinaryOp>().is_ok()
+                || name.parse::<TernaryOp>().is_ok()
+        }
     }
 }
 
 fn parse_expr(node: Node) -> Result<Expression> {
-    let mut exprs = get_exprs(&node);
-    let mut texts = get_texts(&node);
+    let mut unknown_attributes = Vec::new();
+    for attr in node.attributes() {
+        // `target` of `<name>` is the only attribute modeled in expressions
+        let known = node.tag_name().name() == "name"
+            && attr.namespace().is_none()
+            && attr.name() == "target";
+        if !known {
+            push_unknown_attribute(&mut unknown_attributes, node, attr);
+        }
+    }
 
-    macro_rules! next {
-        ($iter:expr) => {
-            try_next!($iter, "Expect expression")
-        };
+    let expr = parse_expr_element(node)?;
+
+    if unknown_attributes.is_empty() {
+        Ok(expr)
+    } else {
+        Ok(Expression::WithUnknownAttributes(
+            Box::new(expr),
+            unknown_attributes,
+        ))
     }
+}
 
-    match node.tag_name().name() {
-        "string" => Ok(Value::String(try_text!(node).into()).into()),
-        "langset" => Ok(Value::LangSet(try_text!(node).into()).into()),
-        "double" => Ok(Value::Double(try_text!(node).parse()?).into()),
-        "int" => Ok(Value::Int(try_text!(node).parse()?).into()),
-        "bool" => Ok(Value::Bool(try_text!(node).parse()?).into()),
-        "const" => Ok(Value::Constant(try_text!(node).parse()?).into()),
-        "matrix" => Ok(Expression::Matrix(Box::new([
-            next!(exprs)?,
-            next!(exprs)?,
-            next!(exprs)?,
-            next!(exprs)?,
-        ]))),
-        "charset" => {
-            let charset = node
-                .children()
-                .filter_map(|c| parse_int_or_range(c).ok())
-                .collect();
-
-            Ok(Value::CharSet(charset).into())
-        }
-        "range" => Ok(Value::Range(next!(texts).parse()?, next!(texts).parse()?).into()),
-        "name" => {
-            let mut target = PropertyTarget::default();
-            parse_attrs!(node, {
-                "target" => target,
-            });
-            let kind = try_text!(node).parse()?;
-
-            Ok(Value::Property(target, kind).into())
+fn parse_expr_element(node: Node) -> Result<Expression> {
+    located(node, || {
+        let mut exprs = get_exprs(&node);
+        let mut texts = get_texts(&node);
+
+        macro_rules! next {
+            ($iter:expr) => {
+                try_next!($iter, FormatError::MissingExpression)
+            };
         }
-        name => {
-            if let Ok(list_op) = name.parse() {
-                Ok(Expression::List(
-                    list_op,
-                    exprs.collect::<Result<Vec<_>>>()?,
-                ))
-            } else if let Ok(unary_op) = name.parse() {
-                Ok(Expression::Unary(unary_op, Box::new(next!(exprs)?)))
-            } else if let Ok(binary_op) = name.parse() {
-                Ok(Expression::Binary(
-                    binary_op,
-                    Box::new([next!(exprs)?, next!(exprs)?]),
-                ))
-            } else if let Ok(ternary_op) = name.parse() {
-                Ok(Expression::Ternary(
-                    ternary_op,
-                    Box::new([next!(exprs)?, next!(exprs)?, next!(exprs)?]),
-                ))
-            } else {
-                Err(Error::InvalidFormat(format!(
-                    "Unknown expression: {:?}",
-                    node.tag_name(),
-                )))
+
+        match node.tag_name().name() {
+            // `<string></string>` is an empty string
+            "string" => Ok(Value::String(node.text().unwrap_or_default().into()).into()),
+            "langset" => {
+                let mut strings = node
+                    .children()
+                    .filter(|c| c.has_tag_name("string"))
+                    .peekable();
+
+                // `<langset>ko-KR</langset>` is accepted too
+                let langset = if strings.peek().is_some() {
+                    strings.filter_map(|c| c.text()).collect()
+                } else {
+                    LangSet::parse(try_text!(node))
+                };
+
+                Ok(Value::LangSet(langset).into())
+            }
+            "double" => Ok(Value::Double(try_text!(node).parse()?).into()),
+            "int" => Ok(Value::Int(parse_int(try_text!(node))?).into()),
+            "bool" => Ok(Value::Bool(try_text!(node).parse()?).into()),
+            "const" => Ok(Value::Constant(try_text!(node).parse()?).into()),
+            "matrix" => {
+                let exprs = [next!(exprs)?, next!(exprs)?, next!(exprs)?, next!(exprs)?];
+
+                let doubles: Vec<Double> = exprs
+                    .iter()
+                    .filter_map(|e| match e {
+                        Expression::Simple(Value::Double(d)) => Some(*d),
+                        _ => None,
+                    })
+                    .collect();
+
+                // constant matrix is a value, so a written `Value::Matrix` is parsed back the same
+                match doubles[..] {
+                    [xx, xy, yx, yy] => Ok(Value::Matrix([xx, xy, yx, yy]).into()),
+                    _ => Ok(Expression::Matrix(Box::new(exprs))),
+                }
+            }
+            "charset" => {
+                let charset = node
+                    .children()
+                    .filter(|c| c.is_element())
+                    .map(parse_code_points)
+                    .collect::<Result<CharSet>>()?;
+
+                Ok(Value::CharSet(charset).into())
+            }
+            "range" => Ok(Value::Range(parse_int(next!(texts))?, parse_int(next!(texts))?).into()),
+            "name" => {
+                let mut target = PropertyTarget::default();
+                parse_attrs!(node, {
+                    "target" => target,
+                });
+                let kind = try_text!(node).parse()?;
+
+                Ok(Value::Property(target, kind).into())
+            }
+            name => {
+                if let Ok(list_op) = name.parse() {
+                    Ok(Expression::List(
+                        list_op,
+                        exprs.collect::<Result<Vec<_>>>()?,
+                    ))
+                } else if let Ok(unary_op) = name.parse() {
+                    Ok(Expression::Unary(unary_op, Box::new(next!(exprs)?)))
+                } else if let Ok(binary_op) = name.parse() {
+                    Ok(Expression::Binary(
+                        binary_op,
+                        Box::new([next!(exprs)?, next!(exprs)?]),
+                    ))
+                } else if let Ok(ternary_op) = name.parse() {
+                    Ok(Expression::Ternary(
+                        ternary_op,
+                        Box::new([next!(exprs)?, next!(exprs)?, next!(exprs)?]),
+                    ))
+                } else {
+                    Err(Error::InvalidFormat(FormatError::UnknownExpression(
+                        name.into(),
+                    )))
+                }
             }
         }
-    }
+    })
 }
 
 fn get_exprs<'a>(node: &'a Node) -> impl Iterator<Item = Result<Expression>> + 'a {
@@ -357,7 +676,68 @@ mod tests {
         test_parse_charset,
         parse_expr,
         "<charset><range><int>0</int><int>123</int></range></charset>",
-        Expression::from(vec![IntOrRange::Range(0, 123)]),
+        Expression::from(CharSet::from_ranges([(0, 123)])),
+    );
+
+    make_parse_test!(
+        test_parse_charset_hex,
+        parse_expr,
+        "<charset><int>0x41</int><range><int> 0x20 </int><int>0x3f</int></range></charset>",
+        Expression::from(CharSet::from_ranges([(0x20, 0x3f), (0x41, 0x41)])),
+    );
+
+    #[test]
+    fn test_parse_namespaced_attribute() {
+        let parts = parse_config_parts(
+            r#"<fontconfig xmlns:x="urn:x"><match x:target="font"><edit name="family" x:a="1"><string/></edit></match></fontconfig>"#,
+        )
+        .unwrap();
+        let m = match &parts[0] {
+            ConfigPart::Match(m) => m,
+            part => panic!("Unexpected part {:?}", part),
+        };
+
+        k9::assert_equal!(m.target, MatchTarget::Pattern);
+        k9::assert_equal!(
+            m.unknown_attributes,
+            vec![
+                ("x:target".into(), "font".into()),
+                ("xmlns:x".into(), "urn:x".into()),
+            ]
+        );
+        k9::assert_equal!(m.edits[0].value, Property::Family(Value::from("").into()));
+    }
+
+    #[test]
+    fn test_parse_charset_error() {
+        let doc = roxmltree::Document::parse(
+            "<charset>\n  <int>0x41</int>\n  <range><int>-1</int><int>2</int></range>\n</charset>",
+        )
+        .unwrap();
+        let err = parse_expr(doc.root_element()).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            Error::InvalidFormat(FormatError::NegativeCodePoint(-1))
+        ));
+        k9::assert_equal!(err.location().unwrap().line, 3);
+
+        let doc = roxmltree::Document::parse("<charset><int>x</int></charset>").unwrap();
+        let err = parse_expr(doc.root_element()).unwrap_err();
+        assert!(matches!(err.kind(), Error::ParseIntError(_)));
+    }
+
+    make_parse_test!(
+        test_parse_int_signed,
+        parse_expr,
+        "<int>-010</int>",
+        Expression::from(-8),
+    );
+
+    make_parse_test!(
+        test_parse_range_hex,
+        parse_expr,
+        "<range><int>-1</int><int>0xff</int></range>",
+        Expression::from(Value::Range(-1, 255)),
     );
 
     make_parse_test!(
@@ -382,10 +762,121 @@ mod tests {
         "<range>0<int>10</int></range>",
     );
 
+    #[test]
+    fn test_location() {
+        let parts = parse_config_parts(
+            r#"<fontconfig>
+  <match>
+    <test name="family"><string>mono</string></test>
+    <edit name="family"><string>monospace</string></edit>
+  </match>
+</fontconfig>"#,
+        )
+        .unwrap();
+
+        let m = match &parts[0] {
+            ConfigPart::Match(m) => m,
+            part => panic!("Unexpected part {:?}", part),
+        };
+        let pos = |l: &Location| (l.file.is_none(), l.line, l.column);
+
+        k9::assert_equal!(pos(parts[0].location().unwrap()), (true, 2, 3));
+        k9::assert_equal!(pos(&m.tests[0].location), (true, 3, 5));
+        k9::assert_equal!(pos(&m.edits[0].location), (true, 4, 5));
+    }
+
+    #[test]
+    fn test_error_location() {
+        let err = parse_config_parts(
+            r#"<fontconfig>
+  <match>
+    <edit name="size"><int>12f</int></edit>
+    <edit name="family"><plus><string>a</string></plus></edit>
+  </match>
+</fontconfig>"#,
+        )
+        .unwrap_err();
+
+        let location = err.location().unwrap();
+        k9::assert_equal!((location.line, location.column), (3, 23));
+        assert!(matches!(err.kind(), Error::ParseIntError(_)));
+
+        let err = parse_config_parts(
+            r#"<fontconfig>
+  <match>
+    <edit name="family" />
+  </match>
+</fontconfig>"#,
+        )
+        .unwrap_err();
+
+        let location = err.location().unwrap();
+        k9::assert_equal!((location.line, location.column), (3, 5));
+        assert!(matches!(
+            err.kind(),
+            Error::InvalidFormat(FormatError::EmptyEdit)
+        ));
+    }
+
+    #[test]
+    fn test_unknown_preserved() {
+        let parts = parse_config_parts(
+            r#"<fontconfig>
+  <cache>~/.fonts.cache</cache>
+  <match>
+    <test name="family" x-test="1"><string>a</string></test>
+    <edit name="family"><string>b</string><string>c</string><bar /></edit>
+    <foo />
+  </match>
+</fontconfig>"#,
+        )
+        .unwrap();
+
+        k9::assert_equal!(
+            parts[0],
+            ConfigPart::Unknown(UnknownElement {
+                name: "cache".into(),
+                children: vec![UnknownNode::Text("~/.fonts.cache".into())],
+                ..Default::default()
+            })
+        );
+
+        let m = match &parts[1] {
+            ConfigPart::Match(m) => m,
+            part => panic!("Unexpected part {:?}", part),
+        };
+        k9::assert_equal!(
+            m.tests[0].unknown_attributes,
+            vec![("x-test".to_string(), "1".to_string())]
+        );
+        k9::assert_equal!(m.edits[0].extra_values, vec![Value::from("c").into()]);
+        k9::assert_equal!(
+            m.edits[0].unknown,
+            vec![UnknownElement {
+                name: "bar".into(),
+                ..Default::default()
+            }]
+        );
+        k9::assert_equal!(
+            m.unknown,
+            vec![UnknownElement {
+                name: "foo".into(),
+                ..Default::default()
+            }]
+        );
+    }
+
     make_parse_test!(
         test_langset,
         parse_expr,
         "<langset>ko-KR</langset>",
         Expression::from(Value::LangSet("ko-KR".into())),
     );
+
+    make_parse_test!(
+        test_langset_strings,
+        parse_expr,
+        "<langset>\n  <string>en</string>\n  <string>ko_KR</string>\n</langset>",
+        Expression::from(Value::LangSet("en|ko-kr".into())),
+    );
 }
@@ -0,0 +1,432 @@
+//! Splice editing of a config file
+//!
+//! Every top-level part and every element inside it keeps its byte range in the source text.
+//! Edits only rewrite the range of the edited element, so everything else is kept byte-identical,
+//! including comments and layout around a replaced `<edit>` in the same `<match>`.
+//!
+//! The replaced element itself is written from scratch,
+//! so replace the innermost element which changes, e.g. the `<bool>` of an `<edit>` to toggle it.
+
+use crate::parser::parse_config;
+use crate::*;
+
+use std::ops::Range;
+
+/// Config source text with its parsed top-level parts
+#[derive(Clone, Debug)]
+pub struct PartEditor {
+    source: String,
+    parts: Vec<PartNode>,
+}
+
+/// A top-level part and where it is in the source text
+#[derive(Clone, Debug, PartialEq)]
+pub struct PartNode {
+    /// Byte range of the element
+    pub range: Range<usize>,
+    pub part: ConfigPart,
+    /// Child elements, e.g. `<test>` and `<edit>` of `<match>`
+    pub children: Vec<ElementNode>,
+}
+
+/// An element inside a part and where it is in the source text
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ElementNode {
+    pub name: String,
+    /// Byte range of the element
+    pub range: Range<usize>,
+    pub children: Vec<ElementNode>,
+}
+
+impl PartEditor {
+    pub fn parse(source: impl Into<String>) -> Result<Self> {
+        let source = source.into();
+        let parts = parse_nodes(&source)?;
+
+        Ok(Self { source, parts })
+    }
+
+    /// Current source text
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    pub fn into_string(self) -> String {
+        self.source
+    }
+
+    pub fn parts(&self) -> &[PartNode] {
+        &self.parts
+    }
+
+    /// Replace the part at `index` with `part`, keeping its indentation
+    ///
+    /// The whole element is rewritten, including comments inside it.
+    /// Use [`Self::replace_element`] to keep the rest of the part.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn replace(&mut self, index: usize, part: &ConfigPart) -> Result<()> {
+        let range = self.parts[index].range.clone();
+        let indent = self.indent_at(range.start).to_string();
+        let xml = indent_xml(&part.to_xml(), &indent);
+
+        self.edit(range, &xml)
+    }
+
+    /// Replace the element at `path` in the part at `index` with `xml`, keeping its indentation
+    ///
+    /// `path` is indices of child elements from the part, comments and text are not counted.
+    /// For example, `&[1, 0]` is the value of the second `<test>` or `<edit>` in `<match>`.
+    /// Only the element is rewritten, comments and formatting around it are kept.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` or `path` is out of bounds, or `path` is empty.
+    pub fn replace_element(&mut self, index: usize, path: &[usize], xml: &str) -> Result<()> {
+        let (first, rest) = path.split_first().expect("path should not be empty");
+        let node = rest
+            .iter()
+            .fold(&self.parts[index].children[*first], |node, i| {
+                &node.children[*i]
+            });
+        let range = node.range.clone();
+        let indent = self.indent_at(range.start).to_string();
+        let xml = indent_xml(xml, &indent);
+
+        self.edit(range, &xml)
+    }
+
+    /// Replace `<test>` at `test_index` of `<match>` at `index` with `test`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the part at `index` doesn't have the `<test>`.
+    pub fn replace_test(&mut self, index: usize, test_index: usize, test: &Test) -> Result<()> {
+        let child = self.child_index(index, "test", test_index);
+        self.replace_element(index, &[child], &test.to_xml())
+    }
+
+    /// Replace `<edit>` at `edit_index` of `<match>` at `index` with `edit`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the part at `index` doesn't have the `<edit>`.
+    pub fn replace_edit(&mut self, index: usize, edit_index: usize, edit: &Edit) -> Result<()> {
+        let child = self.child_index(index, "edit", edit_index);
+        self.replace_element(index, &[child], &edit.to_xml())
+    }
+
+    /// Index of the `n`th `name` element among the children of the part at `index`
+    fn child_index(&self, index: usize, name: &str, n: usize) -> usize {
+        self.parts[index]
+            .children
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.name == name)
+            .nth(n)
+            .unwrap_or_else(|| panic!("part {} doesn't have <{}> {}", index, name, n))
+            .0
+    }
+
+    /// Remove the part at `index`
+    ///
+    /// The line is removed as well when the part is the only thing in the line.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn remove(&mut self, index: usize) -> Result<()> {
+        let mut range = self.parts[index].range.clone();
+        let line_start = self.source[..range.start]
+            .rfind('\n')
+            .map_or(0, |pos| pos + 1);
+        let rest = &self.source[range.end..];
+        let line_end = rest
+            .find('\n')
+            .map_or(self.source.len(), |pos| range.end + pos + 1);
+
+        if self.source[line_start..range.start].trim().is_empty()
+            && self.source[range.end..line_end].trim().is_empty()
+        {
+            range = line_start..line_end;
+        }
+
+        self.edit(range, "")
+    }
+
+    /// Insert `part` before the part at `index`, or at the end when `index` is the number of parts
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the number of parts.
+    pub fn insert(&mut self, index: usize, part: &ConfigPart) -> Result<()> {
+        assert!(
+            index <= self.parts.len(),
+            "insertion index (is {}) should be <= len (is {})",
+            index,
+            self.parts.len()
+        );
+
+        match self.parts.get(index) {
+            Some(node) => {
+                let pos = node.range.start;
+                let indent = self.indent_at(pos).to_string();
+                let xml = indent_xml(&part.to_xml(), &indent);
+                self.edit(pos..pos, &format!("{}\n{}", xml, indent))
+            }
+            None => {
+                let indent = match self.parts.last() {
+                    Some(node) => self.indent_at(node.range.start).to_string(),
+                    None => "  ".to_string(),
+                };
+                let xml = indent_xml(&part.to_xml(), &indent);
+                let (pos, self_closing) = self.root_end()?;
+
+                if self_closing {
+                    // `<fontconfig/>` -> `<fontconfig>...</fontconfig>`
+                    let end = self.source[..pos].rfind("/>").unwrap_or(pos);
+                    let text = format!(">\n{}{}\n</fontconfig>", indent, xml);
+                    self.edit(end..end + 2, &text)
+                } else {
+                    // keep the indentation of closing tag
+                    let line_start = self.source[..pos].rfind('\n').map_or(0, |p| p + 1);
+                    if self.source[line_start..pos].trim().is_empty() {
+                        let text = format!("{}{}\n", indent, xml);
+                        self.edit(line_start..line_start, &text)
+                    } else {
+                        let text = format!("\n{}{}\n", indent, xml);
+                        self.edit(pos..pos, &text)
+                    }
+                }
+            }
+        }
+    }
+
+    /// Whitespace before `pos` in the same line
+    fn indent_at(&self, pos: usize) -> &str {
+        let line_start = self.source[..pos].rfind('\n').map_or(0, |p| p + 1);
+        let prefix = &self.source[line_start..pos];
+
+        if prefix.trim().is_empty() {
+            prefix
+        } else {
+            ""
+        }
+    }
+
+    /// Position of `</fontconfig>`, or end of `<fontconfig/>` with `true`
+    fn root_end(&self) -> Result<(usize, bool)> {
+        let doc = parse_xml(&self.source)?;
+        let root = doc.root_element();
+        let range = root.range();
+        let text = &self.source[range.clone()];
+
+        if text.ends_with("/>") && !text.contains("</") {
+            Ok((range.end, true))
+        } else {
+            let pos = text.rfind("</").map_or(range.end, |p| range.start + p);
+            Ok((pos, false))
+        }
+    }
+
+    /// Apply text edit and parse again, the document is unchanged on error
+    fn edit(&mut self, range: Range<usize>, text: &str) -> Result<()> {
+        let mut source = self.source.clone();
+        source.replace_range(range, text);
+
+        self.parts = parse_nodes(&source)?;
+        self.source = source;
+
+        Ok(())
+    }
+}
+
+fn parse_xml(source: &str) -> Result<roxmltree::Document<'_>> {
+    Ok(roxmltree::Document::parse_with_options(
+        source,
+        roxmltree::ParsingOptions {
+            allow_dtd: true,
+            ..Default::default()
+        },
+    )?)
+}
+
+fn parse_nodes(source: &str) -> Result<Vec<PartNode>> {
+    let doc = parse_xml(source)?;
+    let mut diagnostics = Diagnostics::new();
+    let elements = element_nodes(doc.root_element());
+    let parts = parse_config(&doc, None, &ParseOptions::default(), &mut diagnostics)?;
+
+    elements
+        .into_iter()
+        .zip(parts)
+        .map(|(e, part)| {
+            Ok(PartNode {
+                range: e.range,
+                part: part?,
+                children: e.children,
+            })
+        })
+        .collect()
+}
+
+fn element_nodes(node: roxmltree::Node) -> Vec<ElementNode> {
+    node.children()
+        .filter(|c| c.is_element())
+        .map(|c| ElementNode {
+            name: c.tag_name().name().into(),
+            range: c.range(),
+            children: element_nodes(c),
+        })
+        .collect()
+}
+
+/// Indent every line except the first one, trailing newline is removed
+fn indent_xml(xml: &str, indent: &str) -> String {
+    xml.trim_end_matches('\n')
+        .split('\n')
+        .enumerate()
+        .map(|(i, line)| {
+            if i == 0 {
+                line.to_string()
+            } else {
+                format!("{}{}", indent, line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"<?xml version="1.0"?>
+<!DOCTYPE fontconfig SYSTEM "urn:fontconfig:fonts.dtd">
+<fontconfig>
+  <!-- keep me -->
+  <dir>/a</dir>   <!-- trailing -->
+  <match>
+    <edit name="antialias" mode="assign"><bool>true</bool></edit>
+  </match>
+  <dir>/b</dir>
+</fontconfig>
+"#;
+
+    fn dir(path: &str) -> ConfigPart {
+        ConfigPart::Dir(Dir {
+            path: path.into(),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn splice_parts() {
+        let doc = PartEditor::parse(SOURCE).unwrap();
+
+        k9::assert_equal!(doc.parts().len(), 3);
+        k9::assert_equal!(&SOURCE[doc.parts()[0].range.clone()], "<dir>/a</dir>");
+        k9::assert_equal!(doc.parts()[2].part, dir("/b"));
+    }
+
+    #[test]
+    fn splice_edits() {
+        let mut doc = PartEditor::parse(SOURCE).unwrap();
+
+        let mut m = match &doc.parts()[1].part {
+            ConfigPart::Match(m) => m.clone(),
+            part => panic!("Unexpected part {:?}", part),
+        };
+        m.edits[0].value = Property::Antialias(Value::Bool(false).into());
+        doc.replace(1, &ConfigPart::Match(m)).unwrap();
+        doc.remove(2).unwrap();
+        doc.insert(0, &dir("/c")).unwrap();
+        doc.insert(3, &ConfigPart::ResetDirs).unwrap();
+
+        k9::assert_equal!(
+            doc.as_str(),
+            r#"<?xml version="1.0"?>
+<!DOCTYPE fontconfig SYSTEM "urn:fontconfig:fonts.dtd">
+<fontconfig>
+  <!-- keep me -->
+  <dir>/c</dir>
+  <dir>/a</dir>   <!-- trailing -->
+  <match>
+    <edit name="antialias">
+      <bool>false</bool>
+    </edit>
+  </match>
+  <reset-dirs />
+</fontconfig>
+"#
+        );
+    }
+
+    #[test]
+    fn splice_nested() {
+        let source = r#"<fontconfig>
+  <!-- rendering -->
+  <match target="font">
+    <!-- antialias -->
+    <edit name="antialias" mode="assign"><bool>true</bool></edit>   <!-- on -->
+    <edit  name="hinting"   mode="assign">
+      <bool>true</bool>
+    </edit>
+  </match>
+</fontconfig>
+"#;
+        let mut doc = PartEditor::parse(source).unwrap();
+
+        let mut m = match &doc.parts()[0].part {
+            ConfigPart::Match(m) => m.clone(),
+            part => panic!("Unexpected part {:?}", part),
+        };
+        k9::assert_equal!(doc.parts()[0].children[1].name, "edit");
+        k9::assert_equal!(
+            &source[doc.parts()[0].children[1].children[0].range.clone()],
+            "<bool>true</bool>"
+        );
+
+        // toggle the value of the second `<edit>`, every other byte is kept
+        let value = Expression::from(Value::Bool(false)).to_xml();
+        doc.replace_element(0, &[1, 0], &value).unwrap();
+        let pos = source.rfind("<bool>true</bool>").unwrap();
+        let expected = format!(
+            "{}<bool>false</bool>{}",
+            &source[..pos],
+            &source[pos + "<bool>true</bool>".len()..]
+        );
+        k9::assert_equal!(doc.as_str(), expected);
+
+        // whole `<edit>` is written from scratch, but the rest is kept
+        m.edits[0].value = Property::Antialias(Value::Bool(false).into());
+        doc.replace_edit(0, 0, &m.edits[0]).unwrap();
+        k9::assert_equal!(
+            doc.as_str(),
+            r#"<fontconfig>
+  <!-- rendering -->
+  <match target="font">
+    <!-- antialias -->
+    <edit name="antialias">
+      <bool>false</bool>
+    </edit>   <!-- on -->
+    <edit  name="hinting"   mode="assign">
+      <bool>false</bool>
+    </edit>
+  </match>
+</fontconfig>
+"#
+        );
+    }
+
+    #[test]
+    fn splice_insert_empty() {
+        let mut doc = PartEditor::parse("<fontconfig/>").unwrap();
+        doc.insert(0, &dir("/a")).unwrap();
+
+        k9::assert_equal!(doc.as_str(), "<fontconfig>\n  <dir>/a</dir>\n</fontconfig>");
+        k9::assert_equal!(doc.parts()[0].part, dir("/a"));
+    }
+}
@@ -0,0 +1,236 @@
+//! Validate config document against the structure of fontconfig's `fonts.dtd`
+
+use crate::{Error, FormatError, Result};
+use roxmltree::Node;
+
+/// `%expr;` of `fonts.dtd`, `range` is also accepted by fontconfig
+const EXPR: &[&str] = &[
+    "int",
+    "double",
+    "string",
+    "matrix",
+    "bool",
+    "charset",
+    "langset",
+    "range",
+    "name",
+    "const",
+    "or",
+    "and",
+    "eq",
+    "not_eq",
+    "less",
+    "less_eq",
+    "more",
+    "more_eq",
+    "contains",
+    "not_contains",
+    "plus",
+    "minus",
+    "times",
+    "divide",
+    "not",
+    "if",
+    "floor",
+    "ceil",
+    "round",
+    "trunc",
+];
+
+/// `%constant;` of `fonts.dtd`
+const CONSTANT: &[&str] = &[
+    "int", "double", "string", "matrix", "bool", "charset", "langset", "const",
+];
+
+enum Children {
+    Empty,
+    Text,
+    Elements(&'static [&'static str]),
+}
+
+/// Allowed attributes and children of element
+fn element_rule(name: &str) -> Option<(&'static [&'static str], Children)> {
+    use Children::*;
+
+    let rule: (&[&str], Children) = match name {
+        "fontconfig" => (
+            &[],
+            Elements(&[
+                "alias",
+                "cache",
+                "cachedir",
+                "config",
+                "description",
+                "dir",
+                "include",
+                "match",
+                "remap-dir",
+                "reset-dirs",
+                "selectfont",
+            ]),
+        ),
+        "dir" => (&["prefix", "salt"], Text),
+        "cache" => (&[], Text),
+        "cachedir" => (&["prefix"], Text),
+        "description" => (&["domain"], Text),
+        "include" => (&["ignore_missing", "prefix", "deprecated"], Text),
+        "config" => (&[], Elements(&["blank", "rescan"])),
+        "blank" => (&[], Elements(&["int", "range"])),
+        "rescan" => (&[], Elements(&["int"])),
+        "remap-dir" => (&["as-path", "prefix", "salt"], Text),
+        "reset-dirs" => (&[], Empty),
+        "selectfont" => (&[], Elements(&["rejectfont", "acceptfont"])),
+        "rejectfont" | "acceptfont" => (&[], Elements(&["glob", "pattern"])),
+        "glob" => (&[], Text),
+        "pattern" => (&[], Elements(&["patelt"])),
+        "patelt" => (&["name"], Elements(CONSTANT)),
+        "alias" => (
+            &["binding"],
+            Elements(&["test", "family", "prefer", "accept", "default"]),
+        ),
+        "prefer" | "accept" | "default" => (&[], Elements(&["family"])),
+        "family" => (&[], Text),
+        "match" => (&["target"], Elements(&["test", "edit"])),
+        "test" => (
+            &["qual", "name", "target", "ignore-blanks", "compare"],
+            Elements(EXPR),
+        ),
+        "edit" => (&["name", "mode", "binding"], Elements(EXPR)),
+        "int" | "double" | "string" | "bool" | "const" => (&[], Text),
+        "name" => (&["target"], Text),
+        "charset" => (&[], Elements(&["int", "range"])),
+        "range" => (&[], Elements(&["int"])),
+        "langset" => (&[], Elements(&["string"])),
+        name if EXPR.contains(&name) => (&[], Elements(EXPR)),
+        _ => return None,
+    };
+
+    Some(rule)
+}
+
+/// Check `node` and its descendants, returning the first problem found
+pub(crate) fn validate(node: Node) -> Result<()> {
+    let name = node.tag_name().name();
+    let (attrs, children) = match element_rule(name) {
+        Some(rule) => rule,
+        None => return Err(unknown_element(node)),
+    };
+
+    for attr in node.attributes() {
+        // `xml:space` and other namespaced attributes
+        if attr.namespace().is_some() {
+            continue;
+        }
+
+        if !attrs.contains(&attr.name()) {
+            return Err(Error::InvalidFormat(FormatError::UnknownAttribute(
+                name.into(),
+                attr.name().into(),
+            ))
+            .at(node));
+        }
+    }
+
+    for child in node.children() {
+        if child.is_text() {
+            let text = child.text().unwrap_or_default();
+            if !matches!(children, Children::Text) && !text.trim().is_empty() {
+                return Err(
+                    Error::InvalidFormat(FormatError::UnexpectedText(name.into())).at(node),
+                );
+            }
+        } else if child.is_element() {
+            if element_rule(child.tag_name().name()).is_none() {
+                return Err(unknown_element(child));
+            }
+
+            let allowed = match children {
+                Children::Elements(elements) => elements.contains(&child.tag_name().name()),
+                Children::Empty | Children::Text => false,
+            };
+
+            if !allowed {
+                return Err(Error::InvalidFormat(FormatError::UnexpectedElement(
+                    name.into(),
+                    child.tag_name().name().into(),
+                ))
+                .at(child));
+            }
+
+            validate(child)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn unknown_element(node: Node) -> Error {
+    Error::InvalidFormat(FormatError::UnknownElement(node.tag_name().name().into())).at(node)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    fn strict_error(s: &str) -> (FormatError, u32, u32) {
+        let err = parse_config_parts_with_options(s, &ParseOptions { strict: true }).unwrap_err();
+        let location = err.location().unwrap();
+
+        match err.kind() {
+            Error::InvalidFormat(e) => (e.clone(), location.line, location.column),
+            e => panic!("Unexpected error {:?}", e),
+        }
+    }
+
+    #[test]
+    fn strict_rejects() {
+        k9::assert_equal!(
+            strict_error("<fontconfig>\n  <macth />\n</fontconfig>"),
+            (FormatError::UnknownElement("macth".into()), 2, 3)
+        );
+        k9::assert_equal!(
+            strict_error(
+                "<fontconfig>\n  <match><edit name=\"family\" mdoe=\"append\"><string>a</string></edit></match>\n</fontconfig>"
+            ),
+            (
+                FormatError::UnknownAttribute("edit".into(), "mdoe".into()),
+                2,
+                10
+            )
+        );
+        k9::assert_equal!(
+            strict_error("<fontconfig>\n  <match>sans</match>\n</fontconfig>"),
+            (FormatError::UnexpectedText("match".into()), 2, 3)
+        );
+        k9::assert_equal!(
+            strict_error("<fontconfig>\n  <alias><edit name=\"family\" /></alias>\n</fontconfig>"),
+            (
+                FormatError::UnexpectedElement("alias".into(), "edit".into()),
+                2,
+                10
+            )
+        );
+    }
+
+    #[test]
+    fn strict_accepts_valid() {
+        let s = r#"<?xml version="1.0"?>
+<!DOCTYPE fontconfig SYSTEM "urn:fontconfig:fonts.dtd">
+<fontconfig>
+  <!-- comment -->
+  <dir prefix="xdg" xml:space="preserve">fonts</dir>
+  <match target="font">
+    <test name="pixelsize" compare="less"><double>7</double></test>
+    <edit name="matrix" mode="assign">
+      <times><name>matrix</name><matrix><double>1</double><double>0</double><double>0</double><double>1</double></matrix></times>
+    </edit>
+  </match>
+</fontconfig>"#;
+
+        assert_eq!(
+            parse_config_parts_with_options(s, &ParseOptions { strict: true }).unwrap(),
+            parse_config_parts(s).unwrap()
+        );
+        assert!(parse_config_parts("<fontconfig><macth /></fontconfig>").is_ok());
+    }
+}
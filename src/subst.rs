@@ -0,0 +1,406 @@
+//! Apply `<match>` rules to patterns
+//!
+//! This follows `FcConfigSubstituteWithPat` of freedesktop.org's Fontconfig library.
+
+use crate::eval::{compare_value, compare_value_ignore_blanks, eval, Context};
+use crate::*;
+
+use std::borrow::Cow;
+
+impl FontConfig {
+    /// Apply every `<match target="pattern">` rule and `<alias>` to `pattern` in document order
+    ///
+    /// This is equivalent to `FcConfigSubstitute` with `FcMatchPattern`.
+    pub fn substitute(&self, pattern: &mut Pattern) {
+        for m in self.match_rules() {
+            m.substitute(MatchTarget::Pattern, pattern, None);
+        }
+    }
+
+    /// Apply every `<match target="scan">` rule to `font` in document order
+    ///
+    /// Use this on patterns of font files when building font database,
+    /// this is equivalent to `FcConfigSubstitute` with `FcMatchScan`.
+    pub fn substitute_scan(&self, font: &mut Pattern) {
+        for m in self.match_rules() {
+            m.substitute(MatchTarget::Scan, font, None);
+        }
+    }
+
+    /// `<match>` rules of [`Self::rules`] with `<alias>` lowered into [`Match`]
+    ///
+    /// Aliases are only lowered here when the rule wasn't created by [`Rule::new`], e.g. deserialized.
+    pub(crate) fn match_rules(&self) -> impl Iterator<Item = Cow<'_, Match>> {
+        self.rules
+            .iter()
+            .filter_map(|rule| match (&rule.part, &rule.alias_match) {
+                (ConfigPart::Match(m), _) | (ConfigPart::Alias(_), Some(m)) => {
+                    Some(Cow::Borrowed(m))
+                }
+                (ConfigPart::Alias(alias), None) => Some(Cow::Owned(alias.into())),
+                _ => None,
+            })
+    }
+}
+
+impl Test {
+    fn match_target(&self, kind: MatchTarget) -> MatchTarget {
+        match self.target {
+            TestTarget::Default => kind,
+            TestTarget::Pattern => MatchTarget::Pattern,
+            TestTarget::Font => MatchTarget::Font,
+            TestTarget::Scan => MatchTarget::Scan,
+        }
+    }
+}
+
+impl Match {
+    /// Apply this rule to `p` when its target is `kind` and all of its tests succeed
+    ///
+    /// Returns `true` when edits are applied
+    pub(crate) fn substitute(
+        &self,
+        kind: MatchTarget,
+        p: &mut Pattern,
+        p_pat: Option<&Pattern>,
+    ) -> bool {
+        if self.target != kind {
+            return false;
+        }
+
+        // position of the value matched by the first test of each property, edits are relative to it
+        let mut positions: Vec<(PropertyKind, Option<usize>)> = Vec::new();
+
+        for test in self.tests.iter() {
+            let test_kind = test.match_target(kind);
+            let object = test.value.kind();
+            let m = if kind == MatchTarget::Font && test_kind == MatchTarget::Pattern {
+                p_pat
+            } else {
+                Some(&*p)
+            };
+
+            let values = match m.and_then(|m| m.get(&object)) {
+                Some(values) => values,
+                // If there's no such property, `all` matches while `any` does not
+                None if test.qual == TestQual::All => continue,
+                None => return false,
+            };
+
+            let ctx = Context { kind, p, p_pat };
+            let pos = match test.match_values(ctx, values) {
+                Some(pos) => pos,
+                None => return false,
+            };
+
+            // a test on another target is not the target of edits
+            if test_kind == kind && !positions.iter().any(|(k, _)| *k == object) {
+                positions.push((object, Some(pos)));
+            }
+        }
+
+        for edit in self.edits.iter() {
+            let object = edit.value.kind();
+            let pos = positions
+                .iter()
+                .find(|(k, _)| *k == object)
+                .and_then(|(_, pos)| *pos);
+
+            let values = {
+                let ctx = Context { kind, p, p_pat };
+                // only edits relative to the matched value inherit its binding
+                let same_binding = match (edit.mode, pos) {
+                    (EditMode::Assign | EditMode::Prepend | EditMode::Append, Some(pos)) => {
+                        p.get(&object).map_or(EditBinding::Weak, |v| v[pos].1)
+                    }
+                    _ => EditBinding::Weak,
+                };
+                edit.eval_values(ctx, same_binding)
+            };
+            let count = values.len();
+
+            let new_pos = match (edit.mode, pos) {
+                (EditMode::Assign, Some(pos)) => {
+                    p.values_mut(object.clone()).splice(pos..=pos, values);
+                    Some(pos).filter(|_| count > 0)
+                }
+                (EditMode::Assign, None) | (EditMode::AssignReplace, _) => {
+                    p.replace(object.clone(), values);
+                    None
+                }
+                (EditMode::Prepend, Some(pos)) => {
+                    p.values_mut(object.clone()).splice(pos..pos, values);
+                    Some(pos + count)
+                }
+                (EditMode::Prepend, None) | (EditMode::PrependFirst, _) => {
+                    p.values_mut(object.clone()).splice(0..0, values);
+                    pos.map(|pos| pos + count)
+                }
+                (EditMode::Append, Some(pos)) => {
+                    p.values_mut(object.clone())
+                        .splice(pos + 1..pos + 1, values);
+                    Some(pos)
+                }
+                (EditMode::Append, None) | (EditMode::AppendLast, _) => {
+                    p.values_mut(object.clone()).extend(values);
+                    pos
+                }
+                (EditMode::Delete, Some(pos)) => {
+                    p.remove_value(&object, pos);
+                    None
+                }
+                (EditMode::Delete, None) | (EditMode::DeleteAll, _) => {
+                    p.remove(&object);
+                    None
+                }
+            };
+
+            if let Some((_, pos)) = positions.iter_mut().find(|(k, _)| *k == object) {
+                *pos = new_pos;
+            }
+
+            p.remove_empty(&object);
+        }
+
+        true
+    }
+}
+
+impl Test {
+    /// Find the first value in `values` matching this test
+    ///
+    /// Test values are tried in order and the first one matching is used.
+    /// With [`TestQual::All`], every value should match every test value.
+    fn match_values(&self, ctx: Context, values: &[(Value, EditBinding)]) -> Option<usize> {
+        let kind = self.value.kind();
+        let mut ret = None;
+
+        for expr in self.values() {
+            let expected = eval(ctx, &kind, expr)?;
+            let pos = self.match_value(values, &expected);
+
+            if self.qual == TestQual::All {
+                ret = Some(pos?);
+            } else if pos.is_some() {
+                return pos;
+            }
+        }
+
+        ret
+    }
+
+    fn match_value(&self, values: &[(Value, EditBinding)], expected: &Value) -> Option<usize> {
+        let mut ret = None;
+        let compare = if self.ignore_blanks {
+            compare_value_ignore_blanks
+        } else {
+            compare_value
+        };
+
+        for (i, (value, _)) in values.iter().enumerate() {
+            if compare(value, self.compare.into(), expected) {
+                ret.get_or_insert(i);
+                if self.qual != TestQual::All {
+                    break;
+                }
+            } else if self.qual == TestQual::All {
+                return None;
+            }
+        }
+
+        ret
+    }
+}
+
+impl Edit {
+    /// Evaluate the edit values, [`EditBinding::Same`] takes `same_binding`
+    fn eval_values(&self, ctx: Context, same_binding: EditBinding) -> Vec<(Value, EditBinding)> {
+        let binding = match self.binding {
+            EditBinding::Same => same_binding,
+            binding => binding,
+        };
+        let kind = self.value.kind();
+
+        self.values()
+            .filter_map(|expr| eval(ctx, &kind, expr))
+            .map(|value| (value, binding))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn family_pattern(families: &[&str]) -> Pattern {
+        families
+            .iter()
+            .map(|family| (PropertyKind::Family, Value::from(*family)))
+            .collect()
+    }
+
+    fn families(pat: &Pattern) -> Vec<(String, EditBinding)> {
+        pat.get(&PropertyKind::Family)
+            .unwrap_or_default()
+            .iter()
+            .map(|(v, b)| match v {
+                Value::String(s) => (s.clone(), *b),
+                v => panic!("Unexpected family value {:?}", v),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn substitute_relative_edits() {
+        let c = FontConfig::from_xml(
+            r#"<fontconfig>
+    <match>
+        <test name="family"><string>b</string></test>
+        <edit name="family" mode="prepend" binding="strong"><string>p</string></edit>
+        <edit name="family" mode="append"><string>a</string></edit>
+        <edit name="family" mode="assign" binding="same"><string>x</string></edit>
+    </match>
+</fontconfig>"#,
+        );
+
+        let mut pat = family_pattern(&["a0", "B", "c"]);
+        c.substitute(&mut pat);
+
+        k9::assert_equal!(
+            families(&pat),
+            vec![
+                ("a0".into(), EditBinding::Strong),
+                ("p".into(), EditBinding::Strong),
+                ("x".into(), EditBinding::Strong),
+                ("a".into(), EditBinding::Weak),
+                ("c".into(), EditBinding::Strong),
+            ]
+        );
+    }
+
+    #[test]
+    fn substitute_scan_only() {
+        let c = FontConfig::from_xml(
+            r#"<fontconfig>
+    <match target="scan">
+        <test name="family"><string>Broken Sans</string></test>
+        <edit name="weight"><const>bold</const></edit>
+    </match>
+    <match>
+        <edit name="weight"><const>light</const></edit>
+    </match>
+</fontconfig>"#,
+        );
+
+        let mut font = family_pattern(&["Broken Sans"]);
+        font.add(PropertyKind::Weight, Value::Int(80), EditBinding::Strong);
+        c.substitute_scan(&mut font);
+
+        k9::assert_equal!(
+            font.get(&PropertyKind::Weight),
+            Some(&[(Value::Int(200), EditBinding::Weak)][..])
+        );
+    }
+
+    #[test]
+    fn substitute_value_list() {
+        let c = FontConfig::from_xml(
+            r#"<fontconfig>
+    <match>
+        <test name="family"><string>x</string><string>b</string></test>
+        <edit name="family" mode="prepend"><string>p</string><string>q</string></edit>
+    </match>
+</fontconfig>"#,
+        );
+
+        let mut pat = family_pattern(&["a", "b"]);
+        c.substitute(&mut pat);
+
+        k9::assert_equal!(
+            families(&pat),
+            vec![
+                ("a".into(), EditBinding::Strong),
+                ("p".into(), EditBinding::Weak),
+                ("q".into(), EditBinding::Weak),
+                ("b".into(), EditBinding::Strong),
+            ]
+        );
+    }
+
+    #[test]
+    fn substitute_same_binding() {
+        let cases = [
+            (EditMode::Assign, vec![("x", EditBinding::Strong)]),
+            (
+                EditMode::Prepend,
+                vec![("x", EditBinding::Strong), ("a", EditBinding::Strong)],
+            ),
+            (
+                EditMode::Append,
+                vec![("a", EditBinding::Strong), ("x", EditBinding::Strong)],
+            ),
+            (EditMode::AssignReplace, vec![("x", EditBinding::Weak)]),
+            (
+                EditMode::PrependFirst,
+                vec![("x", EditBinding::Weak), ("a", EditBinding::Strong)],
+            ),
+            (
+                EditMode::AppendLast,
+                vec![("a", EditBinding::Strong), ("x", EditBinding::Weak)],
+            ),
+        ];
+
+        for (mode, expected) in cases.iter() {
+            let m = Match {
+                tests: vec![Test {
+                    value: Property::Family("a".into()),
+                    ..Default::default()
+                }],
+                edits: vec![Edit {
+                    mode: *mode,
+                    binding: EditBinding::Same,
+                    value: Property::Family("x".into()),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            };
+
+            let mut pat = family_pattern(&["a"]);
+            assert!(m.substitute(MatchTarget::Pattern, &mut pat, None));
+            let expected: Vec<(String, EditBinding)> =
+                expected.iter().map(|(f, b)| (f.to_string(), *b)).collect();
+            k9::assert_equal!(families(&pat), expected, "{:?}", mode);
+        }
+    }
+
+    #[test]
+    fn substitute_test_qual() {
+        let m = Match {
+            tests: vec![Test {
+                qual: TestQual::All,
+                compare: TestCompare::NotEq,
+                value: Property::Family("mono".into()),
+                ..Default::default()
+            }],
+            edits: vec![Edit {
+                mode: EditMode::AppendLast,
+                value: Property::Family("sans-serif".into()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        let mut pat = family_pattern(&["mono", "serif"]);
+        assert!(!m.substitute(MatchTarget::Pattern, &mut pat, None));
+
+        let mut pat = family_pattern(&["serif"]);
+        assert!(m.substitute(MatchTarget::Pattern, &mut pat, None));
+        k9::assert_equal!(
+            families(&pat),
+            vec![
+                ("serif".into(), EditBinding::Strong),
+                ("sans-serif".into(), EditBinding::Weak),
+            ]
+        );
+    }
+}
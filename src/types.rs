@@ -1,11 +1,18 @@
 mod alias;
+mod builder;
+mod charset;
 mod config;
 mod constant;
 mod dir;
 mod document;
+mod langset;
+mod location;
 mod match_;
+mod pattern;
 mod property;
+mod render;
 mod selectfont;
+mod unknown;
 mod value;
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -17,6 +24,7 @@ pub enum IntOrRange {
 }
 
 pub use self::{
-    alias::*, config::*, constant::*, dir::*, document::*, match_::*, property::*, selectfont::*,
+    alias::*, builder::*, charset::*, config::*, constant::*, dir::*, document::*, langset::*,
+    location::*, match_::*, pattern::*, property::*, render::*, selectfont::*, unknown::*,
     value::*,
 };
@@ -1,3 +1,5 @@
+use crate::*;
+
 /// Alias elements provide a shorthand notation for the set of common match operations needed to substitute one font family for another. They contain a <family> element followed by optional <prefer>, <accept> and <default> elements. Fonts matching the <family> element are edited to prepend the list of <prefer>ed families before the matching <family>, append the <accept>able families after the matching <family> and append the <default> families to the end of the family list.
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -11,4 +13,197 @@ pub struct Alias {
     pub accept: Vec<String>,
     /// `<default>`
     pub default: Vec<String>,
+    /// Binding of the added families
+    pub binding: EditBinding,
+    /// Attributes not modeled by this crate
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub unknown_attributes: Vec<(String, String)>,
+    /// Children other than `<family>`, `<prefer>`, `<accept>` and `<default>`
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub unknown: Vec<UnknownElement>,
+    /// Children of `<prefer>` other than `<family>`
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub prefer_unknown: Vec<UnknownElement>,
+    /// Children of `<accept>` other than `<family>`
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub accept_unknown: Vec<UnknownElement>,
+    /// Children of `<default>` other than `<family>`
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub default_unknown: Vec<UnknownElement>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub location: Location,
+}
+
+/// Lower alias into the equivalent `<match>` rule like fontconfig does
+///
+/// The family is tested ignoring blanks, every generated element takes the location of the alias,
+/// and edits take its binding.
+/// Each non-empty family list becomes a single [`Edit`] holding every family in order,
+/// the first one in [`Edit::value`] and the rest in [`Edit::extra_values`].
+impl From<&Alias> for Match {
+    fn from(alias: &Alias) -> Self {
+        let location = &alias.location;
+        let binding = alias.binding;
+        let edit = |mode, families: &[String]| {
+            let mut families = families.iter().map(|f| Expression::from(f.as_str()));
+
+            Some(Edit {
+                mode,
+                binding,
+                value: Property::Family(families.next()?),
+                extra_values: families.collect(),
+                location: location.clone(),
+                ..Default::default()
+            })
+        };
+
+        let edits = vec![
+            (EditMode::Prepend, &alias.prefer),
+            (EditMode::Append, &alias.accept),
+            (EditMode::AppendLast, &alias.default),
+        ]
+        .into_iter()
+        .filter_map(|(mode, families)| edit(mode, families))
+        .collect();
+
+        Match {
+            target: MatchTarget::Pattern,
+            tests: vec![Test {
+                qual: TestQual::Any,
+                target: TestTarget::Default,
+                compare: TestCompare::Eq,
+                ignore_blanks: true,
+                value: Property::Family(alias.alias.as_str().into()),
+                location: location.clone(),
+                ..Default::default()
+            }],
+            edits,
+            location: location.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<Alias> for Match {
+    fn from(alias: Alias) -> Self {
+        Match::from(&alias)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn families(pat: &Pattern) -> &[(Value, EditBinding)] {
+        pat.get(&PropertyKind::Family).unwrap()
+    }
+
+    #[test]
+    fn alias_to_match_test() {
+        let config = FontConfig::from_xml(
+            r#"<fontconfig>
+    <alias>
+        <family>sans-serif</family>
+        <prefer><family>A</family><family>B</family></prefer>
+        <accept><family>C</family><family>D</family></accept>
+        <default><family>E</family></default>
+    </alias>
+</fontconfig>"#,
+        );
+
+        // one edit per family list
+        let m = match &config.rules[0].part {
+            ConfigPart::Alias(alias) => Match::from(alias),
+            part => panic!("Unexpected part {:?}", part),
+        };
+        let counts: Vec<_> = m.edits.iter().map(|e| e.values().count()).collect();
+        k9::assert_equal!(counts, vec![2, 2, 1]);
+
+        let mut pat: Pattern = vec![
+            (PropertyKind::Family, Value::from("x")),
+            (PropertyKind::Family, Value::from("sans-serif")),
+            (PropertyKind::Family, Value::from("y")),
+        ]
+        .into_iter()
+        .collect();
+        config.substitute(&mut pat);
+
+        let families: Vec<_> = families(&pat).iter().map(|(v, _)| v.clone()).collect();
+        let expected: Vec<Value> = ["x", "A", "B", "sans-serif", "C", "D", "y", "E"]
+            .iter()
+            .map(|f| Value::from(*f))
+            .collect();
+        assert_eq!(families, expected);
+    }
+
+    #[test]
+    fn alias_binding_test() {
+        let config = FontConfig::from_xml(
+            r#"<fontconfig>
+    <alias binding="same">
+        <family>Liberation Sans</family>
+        <default><family>Arial</family></default>
+    </alias>
+</fontconfig>"#,
+        );
+
+        let mut pat = Pattern::new();
+        pat.add(
+            PropertyKind::Family,
+            "Liberation Sans".into(),
+            EditBinding::Strong,
+        );
+        config.substitute(&mut pat);
+
+        k9::assert_equal!(
+            families(&pat),
+            &[
+                (Value::from("Liberation Sans"), EditBinding::Strong),
+                (Value::from("Arial"), EditBinding::Weak),
+            ][..]
+        );
+    }
+
+    #[test]
+    fn alias_ignore_blanks_test() {
+        let config = FontConfig::from_xml(
+            r#"<fontconfig>
+    <alias>
+        <family>DejaVu Sans</family>
+        <prefer><family>Noto Sans</family></prefer>
+    </alias>
+</fontconfig>"#,
+        );
+
+        let mut pat = Pattern::new();
+        pat.add(
+            PropertyKind::Family,
+            "dejavusans".into(),
+            EditBinding::Strong,
+        );
+        config.substitute(&mut pat);
+
+        k9::assert_equal!(
+            families(&pat),
+            &[
+                (Value::from("Noto Sans"), EditBinding::Weak),
+                (Value::from("dejavusans"), EditBinding::Strong),
+            ][..]
+        );
+    }
 }
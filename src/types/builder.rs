@@ -0,0 +1,332 @@
+//! Builder API for writing rules in code
+//!
+//! ```
+//! use fontconfig_parser::{prop, Match};
+//!
+//! let m = Match::pattern()
+//!     .test_eq(prop::Family, "Arial")
+//!     .edit_prepend(prop::Family, "Liberation Sans")
+//!     .strong();
+//!
+//! assert_eq!(m.edits.len(), 1);
+//! ```
+
+use crate::*;
+
+use std::marker::PhantomData;
+
+/// Expression evaluated into a value of type `T`
+///
+/// Only values of the right type convert into this, so a value of the wrong type
+/// for a property is a compile error.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TypedExpr<T> {
+    expr: Expression,
+    _marker: PhantomData<T>,
+}
+
+impl<T> TypedExpr<T> {
+    /// Use `expr` as is, the type of its value isn't checked
+    pub(crate) fn from_expr(expr: Expression) -> Self {
+        Self {
+            expr,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn into_expr(self) -> Expression {
+        self.expr
+    }
+}
+
+macro_rules! typed_from {
+    ($($ty:ty => $($from:ty),+;)+) => {
+        $($(
+            impl From<$from> for TypedExpr<$ty> {
+                fn from(v: $from) -> Self {
+                    Self::from_expr(Value::from(v).into())
+                }
+            }
+        )+)+
+    };
+}
+
+typed_from! {
+    Int => Int, Constant;
+    Double => Double, Int;
+    Bool => Bool;
+    String => String, &str;
+    Matrix => Matrix;
+    CharSet => CharSet;
+    LangSet => LangSet, String, &str;
+    Value => Value;
+}
+
+fn make_property<P: TypedProperty>(prop: P, value: TypedExpr<P::Value>) -> Property {
+    prop.kind().make_property(value.into_expr())
+}
+
+fn typed<T>(expr: impl Into<TypedExpr<T>>) -> Expression {
+    expr.into().into_expr()
+}
+
+impl Expression {
+    /// `<not>`
+    pub fn not(expr: impl Into<TypedExpr<Bool>>) -> TypedExpr<Bool> {
+        TypedExpr::from_expr(Expression::Unary(UnaryOp::Not, Box::new(typed(expr))))
+    }
+
+    /// `<ceil>`, `<floor>`, `<round>` or `<trunc>`, use [`Expression::not`] for `<not>`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `op` is [`UnaryOp::Not`].
+    pub fn unary(op: UnaryOp, expr: impl Into<TypedExpr<Double>>) -> TypedExpr<Int> {
+        assert_ne!(op, UnaryOp::Not, "use Expression::not for <not>");
+        TypedExpr::from_expr(Expression::Unary(op, Box::new(typed(expr))))
+    }
+
+    /// Comparison of two values of the same type
+    pub fn binary<T>(
+        op: BinaryOp,
+        lhs: impl Into<TypedExpr<T>>,
+        rhs: impl Into<TypedExpr<T>>,
+    ) -> TypedExpr<Bool> {
+        TypedExpr::from_expr(Expression::Binary(op, Box::new([typed(lhs), typed(rhs)])))
+    }
+
+    /// `<if>`, `then` is the value when `cond` is true
+    pub fn ternary<T>(
+        op: TernaryOp,
+        cond: impl Into<TypedExpr<Bool>>,
+        then: impl Into<TypedExpr<T>>,
+        otherwise: impl Into<TypedExpr<T>>,
+    ) -> TypedExpr<T> {
+        TypedExpr::from_expr(Expression::Ternary(
+            op,
+            Box::new([typed(cond), typed(then), typed(otherwise)]),
+        ))
+    }
+
+    /// Operator applied to values of the same type, which is the type of the result
+    pub fn list<T, E: Into<TypedExpr<T>>>(
+        op: ListOp,
+        exprs: impl IntoIterator<Item = E>,
+    ) -> TypedExpr<T> {
+        TypedExpr::from_expr(Expression::List(
+            op,
+            exprs.into_iter().map(|e| e.into().into_expr()).collect(),
+        ))
+    }
+
+    /// `<matrix>` with elements `[xx, xy, yx, yy]`
+    pub fn matrix<E: Into<TypedExpr<Double>>>(exprs: [E; 4]) -> TypedExpr<Matrix> {
+        let [xx, xy, yx, yy] = exprs;
+        TypedExpr::from_expr(Expression::Matrix(Box::new([
+            typed(xx),
+            typed(xy),
+            typed(yx),
+            typed(yy),
+        ])))
+    }
+
+    /// `<name>` of a property, typed like the property
+    pub fn name<P: TypedProperty>(target: PropertyTarget, prop: P) -> TypedExpr<P::Value> {
+        TypedExpr::from_expr(Value::Property(target, prop.kind()).into())
+    }
+}
+
+impl Test {
+    pub fn new<P: TypedProperty>(
+        prop: P,
+        compare: TestCompare,
+        value: impl Into<TypedExpr<P::Value>>,
+    ) -> Self {
+        Self {
+            compare,
+            value: make_property(prop, value.into()),
+            ..Default::default()
+        }
+    }
+
+    pub fn eq<P: TypedProperty>(prop: P, value: impl Into<TypedExpr<P::Value>>) -> Self {
+        Self::new(prop, TestCompare::Eq, value)
+    }
+
+    /// Every value of the property should match
+    pub fn all(mut self) -> Self {
+        self.qual = TestQual::All;
+        self
+    }
+
+    pub fn with_target(mut self, target: TestTarget) -> Self {
+        self.target = target;
+        self
+    }
+}
+
+impl Edit {
+    pub fn new<P: TypedProperty>(
+        prop: P,
+        mode: EditMode,
+        value: impl Into<TypedExpr<P::Value>>,
+    ) -> Self {
+        Self {
+            mode,
+            value: make_property(prop, value.into()),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_binding(mut self, binding: EditBinding) -> Self {
+        self.binding = binding;
+        self
+    }
+}
+
+macro_rules! edit_methods {
+    ($($mode:ident: $edit:ident, $match_edit:ident;)+) => {
+        impl Edit {
+            $(
+                pub fn $edit<P: TypedProperty>(prop: P, value: impl Into<TypedExpr<P::Value>>) -> Self {
+                    Self::new(prop, EditMode::$mode, value)
+                }
+            )+
+        }
+
+        impl Match {
+            $(
+                pub fn $match_edit<P: TypedProperty>(
+                    self,
+                    prop: P,
+                    value: impl Into<TypedExpr<P::Value>>,
+                ) -> Self {
+                    self.edit(Edit::$edit(prop, value))
+                }
+            )+
+        }
+    };
+}
+
+edit_methods! {
+    Assign: assign, edit_assign;
+    AssignReplace: assign_replace, edit_assign_replace;
+    Prepend: prepend, edit_prepend;
+    PrependFirst: prepend_first, edit_prepend_first;
+    Append: append, edit_append;
+    AppendLast: append_last, edit_append_last;
+}
+
+impl Match {
+    pub fn new(target: MatchTarget) -> Self {
+        Self {
+            target,
+            ..Default::default()
+        }
+    }
+
+    /// `<match target="pattern">`
+    pub fn pattern() -> Self {
+        Self::new(MatchTarget::Pattern)
+    }
+
+    /// `<match target="font">`
+    pub fn font() -> Self {
+        Self::new(MatchTarget::Font)
+    }
+
+    /// `<match target="scan">`
+    pub fn scan() -> Self {
+        Self::new(MatchTarget::Scan)
+    }
+
+    pub fn test(mut self, test: Test) -> Self {
+        self.tests.push(test);
+        self
+    }
+
+    pub fn test_eq<P: TypedProperty>(self, prop: P, value: impl Into<TypedExpr<P::Value>>) -> Self {
+        self.test(Test::eq(prop, value))
+    }
+
+    pub fn edit(mut self, edit: Edit) -> Self {
+        self.edits.push(edit);
+        self
+    }
+
+    /// Set binding of the last edit to [`EditBinding::Strong`], does nothing without edits
+    pub fn strong(self) -> Self {
+        self.binding(EditBinding::Strong)
+    }
+
+    /// Set binding of the last edit to [`EditBinding::Same`], does nothing without edits
+    pub fn same(self) -> Self {
+        self.binding(EditBinding::Same)
+    }
+
+    fn binding(mut self, binding: EditBinding) -> Self {
+        if let Some(edit) = self.edits.last_mut() {
+            edit.binding = binding;
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_match() {
+        let built = Match::font()
+            .test(Test::eq(prop::Weight, Constant::Bold).all())
+            .test_eq(PropertyKind::Dynamic("x".into()), Value::Bool(true))
+            .edit_assign(
+                prop::PixelSize,
+                Expression::list(
+                    ListOp::Times,
+                    [
+                        Expression::name(PropertyTarget::Default, prop::PixelSize),
+                        1.5.into(),
+                    ],
+                ),
+            )
+            .edit_append(prop::Embolden, true)
+            .same()
+            .edit_prepend(
+                prop::Size,
+                Expression::name(PropertyTarget::Font, prop::Dpi),
+            )
+            .edit_assign(
+                prop::Hinting,
+                Expression::ternary(
+                    TernaryOp::If,
+                    Expression::binary(
+                        BinaryOp::Less,
+                        Expression::name(PropertyTarget::Default, prop::Size),
+                        8.0,
+                    ),
+                    false,
+                    Expression::not(false),
+                ),
+            );
+
+        let parsed = parse_config_parts(
+            r#"<fontconfig>
+    <match target="font">
+        <test qual="all" name="weight"><const>bold</const></test>
+        <test name="x"><bool>true</bool></test>
+        <edit name="pixelsize"><times><name>pixelsize</name><double>1.5</double></times></edit>
+        <edit name="embolden" mode="append" binding="same"><bool>true</bool></edit>
+        <edit name="size" mode="prepend"><name target="font">dpi</name></edit>
+        <edit name="hinting">
+            <if><less><name>size</name><double>8</double></less><bool>false</bool><not><bool>false</bool></not></if>
+        </edit>
+    </match>
+</fontconfig>"#,
+        )
+        .unwrap();
+
+        k9::assert_equal!(parsed, vec![ConfigPart::Match(built)]);
+    }
+}
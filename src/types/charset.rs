@@ -0,0 +1,240 @@
+use crate::{Int, IntOrRange};
+
+use std::iter::FromIterator;
+
+/// Set of Unicode code points
+///
+/// Code points are kept as sorted and merged inclusive ranges, so queries are binary searches
+/// and set operations work on ranges instead of each code point.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(from = "Vec<IntOrRange>", into = "Vec<IntOrRange>")
+)]
+pub struct CharSet {
+    ranges: Vec<(Int, Int)>,
+}
+
+impl CharSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Make charset from inclusive `(first, last)` ranges in any order
+    pub fn from_ranges(ranges: impl IntoIterator<Item = (Int, Int)>) -> Self {
+        Self {
+            ranges: merge_ranges(
+                ranges
+                    .into_iter()
+                    .map(|(b, e)| (b.min(e), b.max(e)))
+                    .collect(),
+            ),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Number of code points
+    pub fn count(&self) -> u64 {
+        self.ranges.iter().map(|&(b, e)| (e - b) as u64 + 1).sum()
+    }
+
+    /// Sorted and merged inclusive `(first, last)` ranges
+    pub fn ranges(&self) -> &[(Int, Int)] {
+        &self.ranges
+    }
+
+    /// Iterate code points in ascending order
+    pub fn iter(&self) -> impl Iterator<Item = Int> + '_ {
+        self.ranges.iter().flat_map(|&(b, e)| b..=e)
+    }
+
+    /// Ranges as `<charset>` children, a single code point is [`IntOrRange::Int`]
+    pub fn int_or_ranges(&self) -> impl Iterator<Item = IntOrRange> + '_ {
+        self.ranges.iter().map(|&(b, e)| {
+            if b == e {
+                IntOrRange::Int(b)
+            } else {
+                IntOrRange::Range(b, e)
+            }
+        })
+    }
+
+    pub fn contains(&self, c: char) -> bool {
+        self.contains_codepoint(c as Int)
+    }
+
+    pub fn contains_codepoint(&self, c: Int) -> bool {
+        // index of the first range which ends at or after `c`
+        let pos = self.ranges.partition_point(|&(_, e)| e < c);
+        matches!(self.ranges.get(pos), Some(&(b, _)) if b <= c)
+    }
+
+    pub fn insert(&mut self, c: char) {
+        self.insert_range(c as Int, c as Int);
+    }
+
+    /// Add inclusive range `first..=last`
+    pub fn insert_range(&mut self, first: Int, last: Int) {
+        *self = self.union(&Self::from_ranges([(first, last)]));
+    }
+
+    pub fn union(&self, other: &CharSet) -> CharSet {
+        let mut ranges = self.ranges.clone();
+        ranges.extend_from_slice(&other.ranges);
+        Self {
+            ranges: merge_ranges(ranges),
+        }
+    }
+
+    pub fn intersection(&self, other: &CharSet) -> CharSet {
+        self.subtract(&self.subtract(other))
+    }
+
+    /// Code points in this set but not in `other`
+    pub fn subtract(&self, other: &CharSet) -> CharSet {
+        Self {
+            ranges: subtract_ranges(&self.ranges, &other.ranges),
+        }
+    }
+
+    pub fn is_subset(&self, other: &CharSet) -> bool {
+        self.subtract(other).is_empty()
+    }
+
+    pub fn is_superset(&self, other: &CharSet) -> bool {
+        other.is_subset(self)
+    }
+}
+
+/// Sort `ranges` and merge overlapping or adjacent ones
+fn merge_ranges(mut ranges: Vec<(Int, Int)>) -> Vec<(Int, Int)> {
+    ranges.sort_unstable();
+
+    let mut merged: Vec<(Int, Int)> = Vec::with_capacity(ranges.len());
+    for (b, e) in ranges {
+        match merged.last_mut() {
+            Some(last) if b <= last.1.saturating_add(1) => last.1 = last.1.max(e),
+            _ => merged.push((b, e)),
+        }
+    }
+    merged
+}
+
+/// Ranges in `a` but not in `b`, both should be merged
+fn subtract_ranges(a: &[(Int, Int)], b: &[(Int, Int)]) -> Vec<(Int, Int)> {
+    let mut ret = Vec::new();
+    // ranges in `b` before `j` end before the current range in `a`
+    let mut j = 0;
+
+    for &(begin, end) in a {
+        while j < b.len() && b[j].1 < begin {
+            j += 1;
+        }
+
+        // `None` when whole range is subtracted
+        let mut rest = Some(begin);
+
+        // don't advance `j` here, the last overlapping range may reach the next range in `a`
+        for &(b_begin, b_end) in b[j..].iter().take_while(|&&(b_begin, _)| b_begin <= end) {
+            let begin = match rest {
+                Some(begin) => begin,
+                None => break,
+            };
+            if b_begin > begin {
+                ret.push((begin, b_begin - 1));
+            }
+            rest = if b_end >= end { None } else { Some(b_end + 1) };
+        }
+
+        if let Some(begin) = rest {
+            ret.push((begin, end));
+        }
+    }
+
+    ret
+}
+
+impl FromIterator<IntOrRange> for CharSet {
+    fn from_iter<T: IntoIterator<Item = IntOrRange>>(iter: T) -> Self {
+        Self::from_ranges(iter.into_iter().map(|c| match c {
+            IntOrRange::Int(i) => (i, i),
+            IntOrRange::Range(b, e) => (b, e),
+        }))
+    }
+}
+
+impl FromIterator<char> for CharSet {
+    fn from_iter<T: IntoIterator<Item = char>>(iter: T) -> Self {
+        Self::from_ranges(iter.into_iter().map(|c| (c as Int, c as Int)))
+    }
+}
+
+impl From<Vec<IntOrRange>> for CharSet {
+    fn from(v: Vec<IntOrRange>) -> Self {
+        v.into_iter().collect()
+    }
+}
+
+impl From<CharSet> for Vec<IntOrRange> {
+    fn from(charset: CharSet) -> Self {
+        charset.int_or_ranges().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn charset_normalize() {
+        let charset: CharSet = vec![
+            IntOrRange::Range(0x30, 0x39),
+            IntOrRange::Int(0x41),
+            IntOrRange::Range(0x3a, 0x3a),
+            IntOrRange::Range(0x2f, 0x10),
+        ]
+        .into();
+
+        k9::assert_equal!(charset.ranges(), &[(0x10, 0x3a), (0x41, 0x41)][..]);
+        k9::assert_equal!(charset.count(), 44);
+        assert!(charset.contains('A'));
+        assert!(charset.contains_codepoint(0x10));
+        assert!(!charset.contains('B'));
+        assert!(!charset.contains('\u{f}'));
+        k9::assert_equal!(
+            Vec::from(charset),
+            vec![IntOrRange::Range(0x10, 0x3a), IntOrRange::Int(0x41)]
+        );
+    }
+
+    #[test]
+    fn charset_ops() {
+        let a = CharSet::from_ranges([(0, 10), (20, 30)]);
+        let b = CharSet::from_ranges([(5, 25)]);
+
+        k9::assert_equal!(a.union(&b), CharSet::from_ranges([(0, 30)]));
+        k9::assert_equal!(
+            a.intersection(&b),
+            CharSet::from_ranges([(5, 10), (20, 25)])
+        );
+        k9::assert_equal!(a.subtract(&b), CharSet::from_ranges([(0, 4), (26, 30)]));
+        assert!(a.is_superset(&CharSet::from_ranges([(1, 2), (21, 22)])));
+        assert!(!a.is_subset(&b));
+
+        let a = CharSet::from_ranges([(0, 10), (20, 30), (40, 50)]);
+        let b = CharSet::from_ranges([(2, 3), (8, 22), (25, 25), (45, 60)]);
+        k9::assert_equal!(
+            a.subtract(&b),
+            CharSet::from_ranges([(0, 1), (4, 7), (23, 24), (26, 30), (40, 44)])
+        );
+        k9::assert_equal!(b.subtract(&a), CharSet::from_ranges([(11, 19), (51, 60)]));
+
+        let mut c: CharSet = "ab".chars().collect();
+        c.insert('c');
+        k9::assert_equal!(c.iter().collect::<Vec<_>>(), vec![0x61, 0x62, 0x63]);
+    }
+}
@@ -1,8 +1,28 @@
-use crate::{Int, IntOrRange};
+use crate::{Int, IntOrRange, Location, UnknownElement};
 
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Config {
     pub blanks: Vec<IntOrRange>,
     pub rescans: Vec<Int>,
+    /// Children other than `<blank>` and `<rescan>`
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub unknown: Vec<UnknownElement>,
+    /// Children of `<blank>` other than `<int>` and `<range>`
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub blank_unknown: Vec<UnknownElement>,
+    /// Children of `<rescan>` other than its first `<int>`
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub rescan_unknown: Vec<UnknownElement>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub location: Location,
 }
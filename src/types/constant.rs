@@ -1,4 +1,4 @@
-use crate::PropertyKind;
+use crate::{Int, PropertyKind};
 
 macro_rules! define_constant {
     (
@@ -15,7 +15,7 @@ macro_rules! define_constant {
         }
 
         impl Constant {
-            pub fn get_value(self, kind: PropertyKind) -> Option<u32> {
+            pub fn get_value(self, kind: PropertyKind) -> Option<Int> {
                 match (self, kind) {
                     $(
                         $(
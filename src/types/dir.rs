@@ -1,9 +1,19 @@
+use crate::Location;
+
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Dir {
     pub prefix: DirPrefix,
     pub salt: String,
     pub path: String,
+    /// Attributes not modeled by this crate
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub unknown_attributes: Vec<(String, String)>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub location: Location,
 }
 
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
@@ -11,6 +21,14 @@ pub struct Dir {
 pub struct CacheDir {
     pub prefix: DirPrefix,
     pub path: String,
+    /// Attributes not modeled by this crate
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub unknown_attributes: Vec<(String, String)>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub location: Location,
 }
 
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
@@ -19,10 +37,18 @@ pub struct Include {
     pub prefix: DirPrefix,
     pub ignore_missing: bool,
     pub path: String,
+    /// Attributes not modeled by this crate
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub unknown_attributes: Vec<(String, String)>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub location: Location,
 }
 
 /// This element contains a directory name where will be mapped as the path 'as-path' in cached information. This is useful if the directory name is an alias (via a bind mount or symlink) to another directory in the system for which cached font information is likely to exist.
-
+///
 /// 'salt' property affects to determine cache filename as same as [`Dir`] element.
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -31,11 +57,20 @@ pub struct RemapDir {
     pub as_path: String,
     pub salt: String,
     pub path: String,
+    /// Attributes not modeled by this crate
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub unknown_attributes: Vec<(String, String)>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub location: Location,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DirPrefix {
+    #[default]
     Default,
     Cwd,
     Xdg,
@@ -57,12 +92,6 @@ parse_enum! {
     (Relative, "relative"),
 }
 
-impl Default for DirPrefix {
-    fn default() -> Self {
-        DirPrefix::Default
-    }
-}
-
 /// Get the location to user home directory.
 ///
 /// This implementation follows `FcConfigHome` function of freedesktop.org's
@@ -87,7 +116,7 @@ fn config_home() -> Result<String, std::env::VarError> {
 fn config_get_file_name(p: &std::path::PathBuf) -> std::path::PathBuf {
     if cfg!(target_os = "windows") {
         // TODO: get config file path properly for Windows
-        return p.clone();
+        p.clone()
     } else {
         std::path::Path::new("/etc/fonts").join(p)
     }
@@ -3,12 +3,14 @@ use crate::*;
 
 use std::collections::HashSet;
 use std::fs;
+use std::io;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 #[derive(Clone, Debug, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ConfigPart {
-    Description(String),
+    Description(Description),
     SelectFont(SelectFont),
     Dir(Dir),
     CacheDir(CacheDir),
@@ -18,6 +20,21 @@ pub enum ConfigPart {
     Alias(Alias),
     RemapDir(RemapDir),
     ResetDirs,
+    /// Element not modeled by this crate, ignored when merging
+    Unknown(UnknownElement),
+}
+
+/// `<description>` of config file
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Description {
+    pub text: String,
+    /// Attributes not modeled by this crate, like `domain`
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub unknown_attributes: Vec<(String, String)>,
 }
 
 #[derive(Clone, Debug, Default, PartialEq)]
@@ -31,39 +48,152 @@ pub struct FontConfig {
     pub config: Config,
     pub aliases: Vec<Alias>,
     pub config_files: HashSet<PathBuf>,
+    /// Lower `<alias>` into [`Match`] and push it to [`Self::matches`] instead of [`Self::aliases`]
+    pub lower_aliases: bool,
+    /// Every merged part except [`ConfigPart::Include`] in evaluation order
+    ///
+    /// Parts of included files are placed where `<include>` is.
+    /// [`Self::substitute`] and [`Self::to_config_parts`] read this list,
+    /// the other fields are summaries of it.
+    pub rules: Vec<Rule>,
+    /// Options used for parsing every merged file
+    pub parse_options: ParseOptions,
+}
+
+/// A [`ConfigPart`] with where it came from
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rule {
+    /// canonicalized path of the config file
+    pub file: PathBuf,
+    /// index of the part in the config file
+    pub index: usize,
+    pub part: ConfigPart,
+    /// `<alias>` in [`Self::part`] lowered into [`Match`], so substitution doesn't lower it on every call
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) alias_match: Option<Match>,
+}
+
+impl Rule {
+    /// Create rule of `part`
+    ///
+    /// Create it again instead of changing [`Self::part`] when the part is an `<alias>`.
+    pub fn new(file: PathBuf, index: usize, part: ConfigPart) -> Self {
+        let alias_match = match &part {
+            ConfigPart::Alias(alias) => Some(alias.into()),
+            _ => None,
+        };
+
+        Self {
+            file,
+            index,
+            part,
+            alias_match,
+        }
+    }
+}
+
+/// Config with the parts of `xml` as rules, for testing substitution without files
+#[cfg(test)]
+impl FontConfig {
+    pub(crate) fn from_xml(xml: &str) -> Self {
+        let rules = parse_config_parts(xml)
+            .unwrap()
+            .into_iter()
+            .enumerate()
+            .map(|(index, part)| Rule::new(Default::default(), index, part))
+            .collect();
+
+        Self {
+            rules,
+            ..Default::default()
+        }
+    }
+}
+
+impl ConfigPart {
+    /// Source location of the part
+    ///
+    /// [`ConfigPart::Description`] and [`ConfigPart::ResetDirs`] don't keep location.
+    pub fn location(&self) -> Option<&Location> {
+        match self {
+            ConfigPart::Description(_) | ConfigPart::ResetDirs => None,
+            ConfigPart::SelectFont(s) => Some(&s.location),
+            ConfigPart::Dir(d) => Some(&d.location),
+            ConfigPart::CacheDir(d) => Some(&d.location),
+            ConfigPart::Include(i) => Some(&i.location),
+            ConfigPart::Match(m) => Some(&m.location),
+            ConfigPart::Config(c) => Some(&c.location),
+            ConfigPart::Alias(a) => Some(&a.location),
+            ConfigPart::RemapDir(d) => Some(&d.location),
+            ConfigPart::Unknown(e) => Some(&e.location),
+        }
+    }
 }
 
 impl FontConfig {
     pub fn merge_config<P: AsRef<Path> + ?Sized>(&mut self, config_path: &P) -> Result<()> {
-        match std::fs::canonicalize(&config_path) {
+        self.merge_config_with_diagnostics(config_path, &mut Diagnostics::new())
+    }
+
+    /// Same as [`Self::merge_config`] but also collect problems which are ignored while merging
+    ///
+    /// Failed includes, unmergeable files in included directory and invalid `<patelt>`s are pushed to `diagnostics`.
+    pub fn merge_config_with_diagnostics<P: AsRef<Path> + ?Sized>(
+        &mut self,
+        config_path: &P,
+        diagnostics: &mut Diagnostics,
+    ) -> Result<()> {
+        let file = match std::fs::canonicalize(config_path) {
             Ok(p) => {
-                if !self.config_files.insert(std::path::PathBuf::from(p)) {
+                if !self.config_files.insert(p.clone()) {
                     return Ok(());
                 }
+                p
             }
             Err(err) => return Err(Error::IoError(err)),
-        }
+        };
 
         let config = fs::read_to_string(config_path.as_ref())?;
+        let source: Arc<Path> = Arc::from(config_path.as_ref());
         let xml_doc = roxmltree::Document::parse_with_options(
             &config,
             roxmltree::ParsingOptions {
                 allow_dtd: true,
                 ..Default::default()
             },
-        )?;
+        )
+        .map_err(|e| {
+            let pos = e.pos();
+            let location = Location {
+                file: Some(source.clone()),
+                line: pos.row,
+                column: pos.col,
+            };
+            Error::Located(Box::new(e.into()), location)
+        })?;
+
+        let parts = parse_config(&xml_doc, Some(source), &self.parse_options, diagnostics)?
+            .collect::<Result<Vec<_>>>()?;
 
-        for part in parse_config(&xml_doc)? {
-            match part? {
+        for (index, part) in parts.into_iter().enumerate() {
+            if !matches!(part, ConfigPart::Include(_)) {
+                self.rules
+                    .push(Rule::new(file.clone(), index, part.clone()));
+            }
+
+            match part {
+                ConfigPart::Alias(alias) if self.lower_aliases => self.matches.push(alias.into()),
                 ConfigPart::Alias(alias) => self.aliases.push(alias),
                 ConfigPart::Config(mut c) => {
                     self.config.rescans.append(&mut c.rescans);
                     self.config.blanks.append(&mut c.blanks);
                 }
-                ConfigPart::Description(_) => {}
+                ConfigPart::Description(_) | ConfigPart::Unknown(_) => {}
                 ConfigPart::Dir(dir) => self.dirs.push(DirData {
                     path: dir.calculate_path(config_path),
                     salt: dir.salt,
+                    location: dir.location,
                 }),
                 ConfigPart::CacheDir(dir) => self.cache_dirs.push(dir.calculate_path(config_path)),
                 ConfigPart::Match(m) => self.matches.push(m),
@@ -73,19 +203,17 @@ impl FontConfig {
                     path: remap.calculate_path(config_path),
                     salt: remap.salt,
                     as_path: remap.as_path,
+                    location: remap.location,
                 }),
                 ConfigPart::Include(dir) => {
                     let include_path = dir.calculate_path(config_path);
 
-                    match self.include(&include_path) {
+                    match self.include(&include_path, &dir.location, diagnostics) {
                         Ok(_) => {}
-                        #[allow(unused_variables)]
-                        Err(err) => {
-                            if !dir.ignore_missing {
-                                #[cfg(feature = "log")]
-                                log::warn!("Failed to include {}: {}", include_path.display(), err);
-                            }
-                        }
+                        // `ignore_missing` only silences the include target not existing
+                        Err(Error::IoError(err))
+                            if dir.ignore_missing && err.kind() == io::ErrorKind::NotFound => {}
+                        Err(err) => diagnostics.push(Severity::Error, dir.location, err),
                     }
                 }
             }
@@ -94,13 +222,18 @@ impl FontConfig {
         Ok(())
     }
 
-    fn include(&mut self, include_path: &Path) -> Result<()> {
+    fn include(
+        &mut self,
+        include_path: &Path,
+        location: &Location,
+        diagnostics: &mut Diagnostics,
+    ) -> Result<()> {
         let meta = fs::metadata(include_path)?;
         let ty = meta.file_type();
 
         // fs::metadata follow symlink so ty is never symlink
         if ty.is_file() {
-            self.merge_config(include_path)?;
+            self.merge_config_with_diagnostics(include_path, diagnostics)?;
         } else if ty.is_dir() {
             let dir = std::fs::read_dir(include_path)?;
             let mut config_paths: Vec<_> = dir
@@ -122,13 +255,9 @@ impl FontConfig {
             config_paths.sort();
 
             for config_path in config_paths {
-                match self.merge_config(&config_path) {
+                match self.merge_config_with_diagnostics(&config_path, diagnostics) {
                     Ok(_) => {}
-                    #[allow(unused_variables)]
-                    Err(err) => {
-                        #[cfg(feature = "log")]
-                        log::warn!("Failed to merge {}: {}", config_path.display(), err);
-                    }
+                    Err(err) => diagnostics.push(Severity::Error, location.clone(), err),
                 }
             }
         }
@@ -150,6 +279,7 @@ macro_rules! define_config_part_from {
 }
 
 define_config_part_from! {
+    Description,
     SelectFont,
     Dir,
     CacheDir,
@@ -168,6 +298,8 @@ pub struct DirData {
     pub path: PathBuf,
     /// 'salt' property affects to determine cache filename. this is useful for example when having different fonts sets on same path at container and share fonts from host on different font path.
     pub salt: String,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub location: Location,
 }
 
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
@@ -180,4 +312,6 @@ pub struct RemapDirData {
     pub salt: String,
     // remapped path
     pub as_path: String,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub location: Location,
 }
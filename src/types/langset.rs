@@ -0,0 +1,240 @@
+use std::collections::BTreeSet;
+use std::fmt;
+use std::iter::FromIterator;
+
+/// Set of RFC-3066-style language tags
+///
+/// Tags are normalized into lowercase with `-` as the separator, so `ko_KR` and `ko-kr` are the same tag.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct LangSet {
+    langs: BTreeSet<String>,
+}
+
+/// Result of comparing two language tags, better results are smaller
+///
+/// This is `FcLangResult` of fontconfig.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LangResult {
+    Equal,
+    /// Same language but different or missing territory like `en` and `en-us`
+    DifferentTerritory,
+    DifferentLang,
+}
+
+/// Lowercase `lang` and use `-` as the separator
+pub(crate) fn normalize_lang(lang: &str) -> String {
+    lang.trim().to_ascii_lowercase().replace('_', "-")
+}
+
+/// Compare language tags like `FcLangCompare`
+///
+/// `und` (undetermined language) isn't the same language as any tag, and `und-` isn't a territory separator.
+pub fn compare_lang(l1: &str, l2: &str) -> LangResult {
+    let (l1, l2) = (normalize_lang(l1), normalize_lang(l2));
+    let (s1, s2) = (l1.as_bytes(), l2.as_bytes());
+    let is_end = |c: Option<&u8>| matches!(c, None | Some(b'-'));
+
+    let mut is_und = s1.starts_with(b"und") && is_end(s1.get(3));
+    let mut result = LangResult::DifferentLang;
+    let mut i = 0;
+
+    loop {
+        let (c1, c2) = (s1.get(i), s2.get(i));
+
+        if c1 != c2 {
+            if !is_und && is_end(c1) && is_end(c2) {
+                result = LangResult::DifferentTerritory;
+            }
+            return result;
+        } else if c1.is_none() {
+            return if is_und { result } else { LangResult::Equal };
+        } else if c1 == Some(&b'-') && !is_und {
+            result = LangResult::DifferentTerritory;
+        }
+
+        i += 1;
+        // after `und-`, it's not the undetermined language anymore
+        if i == 4 {
+            is_und = false;
+        }
+    }
+}
+
+/// Whether `sup` contains `sub` like `FcLangContains`, one of them may be missing the territory
+fn lang_contains(sup: &str, sub: &str) -> bool {
+    let is_territory = |rest: Option<&str>| matches!(rest, Some(rest) if rest.starts_with('-'));
+
+    sup == sub || is_territory(sup.strip_prefix(sub)) || is_territory(sub.strip_prefix(sup))
+}
+
+impl LangSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse `|` separated tags like `en|fr`
+    pub fn parse(s: &str) -> Self {
+        s.split('|').collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.langs.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.langs.len()
+    }
+
+    /// Iterate normalized tags in sorted order
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.langs.iter().map(String::as_str)
+    }
+
+    /// Add `lang`, empty tag is ignored
+    ///
+    /// Returns `true` if it wasn't in the set
+    pub fn insert(&mut self, lang: &str) -> bool {
+        let lang = normalize_lang(lang);
+        !lang.is_empty() && self.langs.insert(lang)
+    }
+
+    pub fn remove(&mut self, lang: &str) -> bool {
+        self.langs.remove(&normalize_lang(lang))
+    }
+
+    pub fn contains(&self, lang: &str) -> bool {
+        self.langs.contains(&normalize_lang(lang))
+    }
+
+    /// Best result of comparing `lang` with tags of this set like `FcLangSetHasLang`
+    pub fn has_lang(&self, lang: &str) -> LangResult {
+        self.iter()
+            .map(|l| compare_lang(l, lang))
+            .min()
+            .unwrap_or(LangResult::DifferentLang)
+    }
+
+    /// Best result of comparing tags of both sets like `FcLangSetCompare`
+    pub fn compare(&self, other: &LangSet) -> LangResult {
+        other
+            .iter()
+            .map(|lang| self.has_lang(lang))
+            .min()
+            .unwrap_or(LangResult::DifferentLang)
+    }
+
+    /// Whether this set covers `lang`, tags missing the territory cover any territory
+    pub fn contains_lang(&self, lang: &str) -> bool {
+        let lang = normalize_lang(lang);
+        self.iter().any(|l| lang_contains(l, &lang))
+    }
+
+    /// Whether this set covers every tag of `other` like `FcLangSetContains`
+    pub fn contains_all(&self, other: &LangSet) -> bool {
+        other.iter().all(|lang| self.contains_lang(lang))
+    }
+
+    /// Whether every tag of `other` is in this set
+    pub fn is_superset(&self, other: &LangSet) -> bool {
+        self.langs.is_superset(&other.langs)
+    }
+
+    pub fn union(&self, other: &LangSet) -> LangSet {
+        Self {
+            langs: self.langs.union(&other.langs).cloned().collect(),
+        }
+    }
+
+    pub fn intersection(&self, other: &LangSet) -> LangSet {
+        Self {
+            langs: self.langs.intersection(&other.langs).cloned().collect(),
+        }
+    }
+
+    pub fn difference(&self, other: &LangSet) -> LangSet {
+        Self {
+            langs: self.langs.difference(&other.langs).cloned().collect(),
+        }
+    }
+}
+
+impl<'a> FromIterator<&'a str> for LangSet {
+    fn from_iter<T: IntoIterator<Item = &'a str>>(iter: T) -> Self {
+        let mut set = Self::new();
+        for lang in iter {
+            set.insert(lang);
+        }
+        set
+    }
+}
+
+impl<'a> From<&'a str> for LangSet {
+    fn from(s: &'a str) -> Self {
+        Self::parse(s)
+    }
+}
+
+/// Tags separated by `|`
+impl fmt::Display for LangSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, lang) in self.iter().enumerate() {
+            if i != 0 {
+                f.write_str("|")?;
+            }
+            f.write_str(lang)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn langset_normalize() {
+        let set = LangSet::parse("ko_KR|EN| ko-kr |");
+
+        k9::assert_equal!(set.iter().collect::<Vec<_>>(), vec!["en", "ko-kr"]);
+        assert!(set.contains("KO_kr"));
+        k9::assert_equal!(set.to_string(), "en|ko-kr");
+    }
+
+    #[test]
+    fn lang_compare() {
+        k9::assert_equal!(compare_lang("ko-KR", "ko_kr"), LangResult::Equal);
+        k9::assert_equal!(
+            compare_lang("zh-tw", "zh-cn"),
+            LangResult::DifferentTerritory
+        );
+        k9::assert_equal!(compare_lang("en", "en-us"), LangResult::DifferentTerritory);
+        k9::assert_equal!(compare_lang("en", "eo"), LangResult::DifferentLang);
+        k9::assert_equal!(compare_lang("und", "und"), LangResult::DifferentLang);
+        k9::assert_equal!(compare_lang("und-zsye", "und-zsye"), LangResult::Equal);
+        k9::assert_equal!(
+            compare_lang("und-zsye", "und-zmth"),
+            LangResult::DifferentLang
+        );
+
+        let set = LangSet::parse("en|ja-jp");
+        k9::assert_equal!(set.has_lang("ja"), LangResult::DifferentTerritory);
+        k9::assert_equal!(set.compare(&LangSet::parse("fr|en")), LangResult::Equal);
+        assert!(set.contains_lang("ja"));
+        assert!(set.contains_all(&LangSet::parse("en-us|ja-JP")));
+        assert!(!set.contains_lang("ja-kr"));
+    }
+
+    #[test]
+    fn langset_ops() {
+        let a = LangSet::parse("en|fr|ja");
+        let b = LangSet::parse("fr|ko");
+
+        k9::assert_equal!(a.union(&b), LangSet::parse("en|fr|ja|ko"));
+        k9::assert_equal!(a.intersection(&b), LangSet::parse("fr"));
+        k9::assert_equal!(a.difference(&b), LangSet::parse("en|ja"));
+        assert!(a.is_superset(&LangSet::parse("ja|EN")));
+        assert!(!a.is_superset(&b));
+    }
+}
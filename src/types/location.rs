@@ -0,0 +1,51 @@
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Where a part of config came from
+///
+/// `line` and `column` are 1-based position of the element's start tag, `file` is `None` when parsed from string.
+///
+/// Every location compares equal to any other, so it doesn't affect equality of the owner.
+/// It is not serialized either.
+#[derive(Clone, Debug, Default)]
+pub struct Location {
+    pub file: Option<Arc<Path>>,
+    pub line: u32,
+    pub column: u32,
+}
+
+impl Location {
+    pub(crate) fn from_node(node: roxmltree::Node, file: Option<&Arc<Path>>) -> Self {
+        let pos = node.document().text_pos_at(node.range().start);
+
+        Self {
+            file: file.cloned(),
+            line: pos.row,
+            column: pos.col,
+        }
+    }
+}
+
+impl PartialEq for Location {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for Location {}
+
+impl Hash for Location {
+    fn hash<H: Hasher>(&self, _state: &mut H) {}
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}:", file.display())?;
+        }
+
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
@@ -4,17 +4,34 @@ mod test;
 pub use self::edit::*;
 pub use self::test::*;
 
+use crate::{Location, UnknownElement};
+
 #[derive(Clone, Debug, Default, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Match {
     pub target: MatchTarget,
     pub tests: Vec<Test>,
     pub edits: Vec<Edit>,
+    /// Attributes not modeled by this crate
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub unknown_attributes: Vec<(String, String)>,
+    /// Children other than `<test>` and `<edit>`
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub unknown: Vec<UnknownElement>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub location: Location,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MatchTarget {
+    #[default]
     Pattern,
     Font,
     Scan,
@@ -26,9 +43,3 @@ parse_enum! {
     (Font, "font"),
     (Scan, "scan"),
 }
-
-impl Default for MatchTarget {
-    fn default() -> Self {
-        MatchTarget::Pattern
-    }
-}
@@ -1,4 +1,4 @@
-use crate::Property;
+use crate::{Expression, Location, Property, UnknownElement};
 
 #[derive(Clone, Debug, Default, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -6,12 +6,40 @@ pub struct Edit {
     pub mode: EditMode,
     pub binding: EditBinding,
     pub value: Property,
+    /// Values after the first one in `value`
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub extra_values: Vec<Expression>,
+    /// Attributes not modeled by this crate
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub unknown_attributes: Vec<(String, String)>,
+    /// Children other than value expressions
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub unknown: Vec<UnknownElement>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub location: Location,
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+impl Edit {
+    /// All value expressions of `<edit>` in order
+    pub fn values(&self) -> impl Iterator<Item = &Expression> {
+        std::iter::once(self.value.expr()).chain(self.extra_values.iter())
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum EditBinding {
     Strong,
+    #[default]
     Weak,
     Same,
 }
@@ -23,15 +51,10 @@ parse_enum! {
     (Same, "same"),
 }
 
-impl Default for EditBinding {
-    fn default() -> Self {
-        EditBinding::Weak
-    }
-}
-
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum EditMode {
+    #[default]
     Assign,
     AssignReplace,
     Prepend,
@@ -53,9 +76,3 @@ parse_enum! {
     (Delete, "delete"),
     (DeleteAll, "delete_all"),
 }
-
-impl Default for EditMode {
-    fn default() -> Self {
-        EditMode::Assign
-    }
-}
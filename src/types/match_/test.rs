@@ -1,4 +1,4 @@
-use crate::Property;
+use crate::{BinaryOp, Expression, Location, Property, UnknownElement};
 
 /// This element contains a single value which is compared with the target ('pattern', 'font', 'scan' or 'default') property "property" (substitute any of the property names seen above).
 /// 'compare' can be one of "eq", "not_eq", "less", "less_eq", "more", "more_eq", "contains" or "not_contains".
@@ -13,12 +13,48 @@ pub struct Test {
     pub qual: TestQual,
     pub target: TestTarget,
     pub compare: TestCompare,
+    /// `ignore-blanks`, strings are compared ignoring blanks with `eq` and `not_eq`
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "is_false"))]
+    pub ignore_blanks: bool,
     pub value: Property,
+    /// Values after the first one in `value`
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub extra_values: Vec<Expression>,
+    /// Attributes not modeled by this crate
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub unknown_attributes: Vec<(String, String)>,
+    /// Children other than value expressions
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub unknown: Vec<UnknownElement>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub location: Location,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+impl Test {
+    /// All value expressions of `<test>` in order
+    pub fn values(&self) -> impl Iterator<Item = &Expression> {
+        std::iter::once(self.value.expr()).chain(self.extra_values.iter())
+    }
+}
+
+#[cfg(feature = "serde")]
+fn is_false(b: &bool) -> bool {
+    !b
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TestTarget {
+    #[default]
     Default,
     Pattern,
     Font,
@@ -33,15 +69,10 @@ parse_enum! {
     (Scan, "scan"),
 }
 
-impl Default for TestTarget {
-    fn default() -> Self {
-        TestTarget::Default
-    }
-}
-
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TestCompare {
+    #[default]
     Eq,
     NotEq,
     Less,
@@ -64,15 +95,25 @@ parse_enum! {
     (NotContains, "not_contains"),
 }
 
-impl Default for TestCompare {
-    fn default() -> Self {
-        TestCompare::Eq
+impl From<TestCompare> for BinaryOp {
+    fn from(compare: TestCompare) -> Self {
+        match compare {
+            TestCompare::Eq => BinaryOp::Eq,
+            TestCompare::NotEq => BinaryOp::NotEq,
+            TestCompare::Less => BinaryOp::Less,
+            TestCompare::LessEq => BinaryOp::LessEq,
+            TestCompare::More => BinaryOp::More,
+            TestCompare::MoreEq => BinaryOp::MoreEq,
+            TestCompare::Contains => BinaryOp::Contains,
+            TestCompare::NotContains => BinaryOp::NotContains,
+        }
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TestQual {
+    #[default]
     Any,
     All,
 }
@@ -82,9 +123,3 @@ parse_enum! {
     (Any, "any"),
     (All, "all"),
 }
-
-impl Default for TestQual {
-    fn default() -> Self {
-        TestQual::Any
-    }
-}
@@ -0,0 +1,204 @@
+use crate::{Double, EditBinding, PropertyKind, Value};
+
+use std::iter::FromIterator;
+
+/// Runtime font pattern
+///
+/// Each property holds an ordered list of values, every value carrying the binding it was added with.
+/// This is used for both requested patterns and fonts.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Pattern {
+    elts: Vec<(PropertyKind, Vec<(Value, EditBinding)>)>,
+}
+
+impl Pattern {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return `true` if pattern has no property
+    pub fn is_empty(&self) -> bool {
+        self.elts.is_empty()
+    }
+
+    /// Iterate properties with their values
+    pub fn iter(&self) -> impl Iterator<Item = (&PropertyKind, &[(Value, EditBinding)])> {
+        self.elts
+            .iter()
+            .map(|(kind, values)| (kind, values.as_slice()))
+    }
+
+    /// Get all values of `kind`
+    pub fn get(&self, kind: &PropertyKind) -> Option<&[(Value, EditBinding)]> {
+        self.elts
+            .iter()
+            .find(|(k, _)| k == kind)
+            .map(|(_, values)| values.as_slice())
+    }
+
+    /// Get the first value of `kind`
+    pub fn get_value(&self, kind: &PropertyKind) -> Option<&Value> {
+        self.get(kind)?.first().map(|(value, _)| value)
+    }
+
+    /// Append `value` to the values of `kind`
+    pub fn add(&mut self, kind: PropertyKind, value: Value, binding: EditBinding) {
+        self.values_mut(kind).push((value, binding));
+    }
+
+    /// Prepend `value` to the values of `kind`
+    pub fn prepend(&mut self, kind: PropertyKind, value: Value, binding: EditBinding) {
+        self.values_mut(kind).insert(0, (value, binding));
+    }
+
+    /// Remove the value of `kind` at `index`
+    pub fn remove_value(
+        &mut self,
+        kind: &PropertyKind,
+        index: usize,
+    ) -> Option<(Value, EditBinding)> {
+        let pos = self.elts.iter().position(|(k, _)| k == kind)?;
+        let values = &mut self.elts[pos].1;
+
+        if index >= values.len() {
+            return None;
+        }
+
+        let value = values.remove(index);
+
+        if values.is_empty() {
+            self.elts.remove(pos);
+        }
+
+        Some(value)
+    }
+
+    /// Remove all values of `kind`
+    pub fn remove(&mut self, kind: &PropertyKind) -> Option<Vec<(Value, EditBinding)>> {
+        let pos = self.elts.iter().position(|(k, _)| k == kind)?;
+        Some(self.elts.remove(pos).1)
+    }
+
+    /// Replace all values of `kind` with `values`, returning previous values
+    pub fn replace(
+        &mut self,
+        kind: PropertyKind,
+        values: impl IntoIterator<Item = (Value, EditBinding)>,
+    ) -> Option<Vec<(Value, EditBinding)>> {
+        let values: Vec<_> = values.into_iter().collect();
+
+        match self.elts.iter().position(|(k, _)| *k == kind) {
+            Some(pos) if values.is_empty() => Some(self.elts.remove(pos).1),
+            Some(pos) => Some(std::mem::replace(&mut self.elts[pos].1, values)),
+            None => {
+                if !values.is_empty() {
+                    self.elts.push((kind, values));
+                }
+                None
+            }
+        }
+    }
+
+    /// Add default values of missing properties
+    ///
+    /// This follows `FcDefaultSubstitute` except for defaults taken from environment like `lang`.
+    pub fn default_substitute(&mut self) {
+        let defaults = [
+            (PropertyKind::Weight, Value::Int(80)),
+            (PropertyKind::Slant, Value::Int(0)),
+            (PropertyKind::Width, Value::Int(100)),
+            (PropertyKind::Hinting, Value::Bool(true)),
+            (PropertyKind::VerticalLayout, Value::Bool(false)),
+            (PropertyKind::AutoHint, Value::Bool(false)),
+            (PropertyKind::GlobalAdvance, Value::Bool(true)),
+            (PropertyKind::Embeddedbitmap, Value::Bool(true)),
+            (PropertyKind::Decorative, Value::Bool(false)),
+            (PropertyKind::Fontversion, Value::Int(0x7fff_ffff)),
+            (PropertyKind::HintStyle, Value::Int(3)),
+        ];
+
+        for (kind, value) in defaults.iter() {
+            if self.get(kind).is_none() {
+                self.add(kind.clone(), value.clone(), EditBinding::Strong);
+            }
+        }
+
+        let size = match self.get_value(&PropertyKind::Size) {
+            Some(Value::Range(begin, end)) => (*begin as Double + *end as Double) * 0.5,
+            Some(value) => value.as_double().unwrap_or(12.0),
+            None => 12.0,
+        };
+
+        if self.get(&PropertyKind::Size).is_none() {
+            self.add(PropertyKind::Size, Value::Double(size), EditBinding::Strong);
+        }
+
+        if self.get(&PropertyKind::PixelSize).is_none() {
+            let scale = self
+                .get_value(&PropertyKind::Scale)
+                .and_then(Value::as_double)
+                .unwrap_or(1.0);
+            let dpi = self
+                .get_value(&PropertyKind::Dpi)
+                .and_then(Value::as_double)
+                .unwrap_or(75.0);
+
+            self.add(
+                PropertyKind::PixelSize,
+                Value::Double(size * scale * dpi / 72.0),
+                EditBinding::Weak,
+            );
+        }
+    }
+
+    /// Values of `kind`, property is created when it doesn't exist
+    ///
+    /// Caller should call [`Self::remove_empty`] after mutation
+    pub(crate) fn values_mut(&mut self, kind: PropertyKind) -> &mut Vec<(Value, EditBinding)> {
+        match self.elts.iter().position(|(k, _)| *k == kind) {
+            Some(pos) => &mut self.elts[pos].1,
+            None => {
+                self.elts.push((kind, Vec::new()));
+                &mut self.elts.last_mut().unwrap().1
+            }
+        }
+    }
+
+    pub(crate) fn remove_empty(&mut self, kind: &PropertyKind) {
+        self.elts
+            .retain(|(k, values)| k != kind || !values.is_empty());
+    }
+}
+
+/// Collect values with [`EditBinding::Strong`] like `FcPatternAdd`
+impl FromIterator<(PropertyKind, Value)> for Pattern {
+    fn from_iter<T: IntoIterator<Item = (PropertyKind, Value)>>(iter: T) -> Self {
+        let mut pat = Pattern::new();
+
+        for (kind, value) in iter {
+            pat.add(kind, value, EditBinding::Strong);
+        }
+
+        pat
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remove_last_value_test() {
+        let mut pat: Pattern = vec![(PropertyKind::Family, Value::from("serif"))]
+            .into_iter()
+            .collect();
+
+        pat.prepend(PropertyKind::Family, "mono".into(), EditBinding::Weak);
+        assert_eq!(pat.get_value(&PropertyKind::Family), Some(&"mono".into()));
+
+        pat.remove_value(&PropertyKind::Family, 0).unwrap();
+        pat.remove_value(&PropertyKind::Family, 0).unwrap();
+        assert!(pat.is_empty());
+    }
+}
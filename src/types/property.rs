@@ -1,4 +1,4 @@
-use crate::{Expression, Value};
+use crate::{Bool, CharSet, Double, Expression, Int, LangSet, Matrix, Value};
 
 macro_rules! define_property {
     (
@@ -26,6 +26,15 @@ macro_rules! define_property {
                     Property::Dynamic(s, _) => PropertyKind::Dynamic(s.clone()),
                 }
             }
+
+            pub fn expr(&self) -> &Expression {
+                match self {
+                    $(
+                        Property::$variant(expr) => expr,
+                    )+
+                    Property::Dynamic(_, expr) => expr,
+                }
+            }
         }
 
         #[derive(Clone, Debug, PartialEq, Eq)]
@@ -46,6 +55,17 @@ macro_rules! define_property {
             |s| Ok(PropertyKind::Dynamic(s.into())),
         }
 
+        impl core::fmt::Display for PropertyKind {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                f.write_str(match self {
+                    $(
+                        PropertyKind::$variant => $name,
+                    )+
+                    PropertyKind::Dynamic(name) => name,
+                })
+            }
+        }
+
         impl PropertyKind {
             pub fn make_property(self, expr: Expression) -> Property {
                 match self {
@@ -56,9 +76,48 @@ macro_rules! define_property {
                 }
             }
         }
+
+        /// Typed markers of properties for the builder API
+        ///
+        /// See [`TypedProperty`]
+        pub mod prop {
+            $(
+                $(#[$attr])*
+                #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+                pub struct $variant;
+            )+
+        }
+
+        $(
+            impl TypedProperty for prop::$variant {
+                type Value = $value_ty;
+
+                fn kind(self) -> PropertyKind {
+                    PropertyKind::$variant
+                }
+            }
+        )+
     };
 }
 
+/// Property with the type of its value known at compile time
+///
+/// Implemented by markers in [`prop`], and by [`PropertyKind`] itself for untyped values.
+pub trait TypedProperty {
+    /// Type of the value, [`Value`] means any type
+    type Value;
+
+    fn kind(self) -> PropertyKind;
+}
+
+impl TypedProperty for PropertyKind {
+    type Value = Value;
+
+    fn kind(self) -> PropertyKind {
+        self
+    }
+}
+
 define_property! {
     /// Font family names
     Family(String, "family"),
@@ -130,7 +189,7 @@ define_property! {
     /// Unicode chars encoded by the font
     Charset(CharSet, "charset"),
     /// List of RFC-3066-style languages this font supports
-    Lang(String, "lang"),
+    Lang(LangSet, "lang"),
     /// Version number of the font
     Fontversion(Int, "fontversion"),
     /// List of layout capabilities in the font
@@ -169,6 +228,7 @@ impl Default for Property {
     }
 }
 
+#[allow(clippy::derivable_impls)]
 impl Default for PropertyKind {
     fn default() -> Self {
         PropertyKind::Family
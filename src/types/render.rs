@@ -0,0 +1,137 @@
+use crate::{Constant, Int, Matrix, Pattern, PropertyKind, Value};
+
+macro_rules! define_int_enum {
+    (
+        $(#[$attr:meta])*
+        $ty:ident, $kind:ident, $default:ident,
+        $(
+            ($variant:ident, $constant:ident),
+        )+
+    ) => {
+        $(#[$attr])*
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+        pub enum $ty {
+            $(
+                $variant,
+            )+
+        }
+
+        impl $ty {
+            /// Convert from the integer value of the property
+            pub fn from_int(i: Int) -> Option<Self> {
+                $(
+                    if Constant::$constant.get_value(PropertyKind::$kind) == Some(i) {
+                        return Some($ty::$variant);
+                    }
+                )+
+                None
+            }
+        }
+
+        #[allow(clippy::derivable_impls)]
+        impl Default for $ty {
+            fn default() -> Self {
+                $ty::$default
+            }
+        }
+    };
+}
+
+define_int_enum! {
+    /// `hintstyle` property
+    HintStyle, HintStyle, Full,
+    (None, Hintnone),
+    (Slight, Hintslight),
+    (Medium, Hintmedium),
+    (Full, Hintfull),
+}
+
+define_int_enum! {
+    /// `rgba` property, subpixel geometry
+    Rgba, Rgba, Unknown,
+    (Unknown, Unknown),
+    (Rgb, Rgb),
+    (Bgr, Bgr),
+    (Vrgb, Vrgb),
+    (Vbgr, Vbgr),
+    (None, None),
+}
+
+define_int_enum! {
+    /// `lcdfilter` property
+    LcdFilter, Lcdfilter, Default,
+    (None, Lcdnone),
+    (Default, Lcddefault),
+    (Light, Lcdlight),
+    (Legacy, Lcdlegacy),
+}
+
+/// Rendering options of a prepared font pattern
+///
+/// Missing or invalid properties take the default value rasterizers use.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RenderSettings {
+    pub antialias: bool,
+    pub hinting: bool,
+    pub hint_style: HintStyle,
+    pub autohint: bool,
+    pub rgba: Rgba,
+    pub lcd_filter: LcdFilter,
+    pub embolden: bool,
+    pub embedded_bitmap: bool,
+    pub matrix: Matrix,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self {
+            antialias: true,
+            hinting: true,
+            hint_style: HintStyle::default(),
+            autohint: false,
+            rgba: Rgba::default(),
+            lcd_filter: LcdFilter::default(),
+            embolden: false,
+            embedded_bitmap: true,
+            matrix: [1.0, 0.0, 0.0, 1.0],
+        }
+    }
+}
+
+impl<'a> From<&'a Pattern> for RenderSettings {
+    fn from(pat: &'a Pattern) -> Self {
+        let mut settings = RenderSettings::default();
+
+        let get_bool = |kind, default| match pat.get_value(&kind) {
+            Some(Value::Bool(b)) => *b,
+            _ => default,
+        };
+        let get_int = |kind| match pat.get_value(&kind) {
+            Some(Value::Int(i)) => Some(*i),
+            _ => None,
+        };
+
+        settings.antialias = get_bool(PropertyKind::Antialias, settings.antialias);
+        settings.hinting = get_bool(PropertyKind::Hinting, settings.hinting);
+        settings.autohint = get_bool(PropertyKind::AutoHint, settings.autohint);
+        settings.embolden = get_bool(PropertyKind::Embolden, settings.embolden);
+        settings.embedded_bitmap = get_bool(PropertyKind::Embeddedbitmap, settings.embedded_bitmap);
+
+        if let Some(hint_style) = get_int(PropertyKind::HintStyle).and_then(HintStyle::from_int) {
+            settings.hint_style = hint_style;
+        }
+        if let Some(rgba) = get_int(PropertyKind::Rgba).and_then(Rgba::from_int) {
+            settings.rgba = rgba;
+        }
+        if let Some(lcd_filter) = get_int(PropertyKind::Lcdfilter).and_then(LcdFilter::from_int) {
+            settings.lcd_filter = lcd_filter;
+        }
+        if let Some(Value::Matrix(matrix)) = pat.get_value(&PropertyKind::Matrix) {
+            settings.matrix = *matrix;
+        }
+
+        settings
+    }
+}
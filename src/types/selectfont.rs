@@ -1,15 +1,194 @@
-use crate::Property;
+use crate::eval::compare_value_listing;
+use crate::util::glob_match;
+use crate::{FontConfig, Location, Pattern, Property, UnknownElement};
+
+use std::path::Path;
 
 #[derive(Clone, Debug, Default, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SelectFont {
     pub rejects: Vec<FontMatch>,
     pub accepts: Vec<FontMatch>,
+    /// Children other than `<rejectfont>` and `<acceptfont>`
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub unknown: Vec<UnknownElement>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub location: Location,
 }
 
 #[derive(Clone, Debug, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FontMatch {
     Glob(String),
-    Pattern(Vec<Property>),
+    Pattern(FontPattern),
+    /// Children of `<acceptfont>` and `<rejectfont>` other than `<glob>` and `<pattern>`, never matches
+    Unknown(UnknownElement),
+}
+
+/// `<pattern>` in `<acceptfont>` and `<rejectfont>`
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FontPattern {
+    pub patelts: Vec<Patelt>,
+    /// Children other than valid `<patelt>`s
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub unknown: Vec<UnknownElement>,
+}
+
+/// `<patelt>`, a property of [`FontPattern`]
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Patelt {
+    pub property: Property,
+    /// Attributes not modeled by this crate
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub unknown_attributes: Vec<(String, String)>,
+}
+
+impl FontMatch {
+    /// Return `true` if font file at `path` with `font` pattern matches
+    ///
+    /// [`FontMatch::Glob`] matches whole `path` where `*` matches any string and `?` matches any character.
+    /// [`FontMatch::Pattern`] matches when every property has at least one of `font`'s values listed by it,
+    /// strings are compared ignoring blanks and case and language sets only need to contain the property's.
+    /// [`FontMatch::Unknown`] never matches.
+    pub fn matches(&self, path: &Path, font: &Pattern) -> bool {
+        match self {
+            FontMatch::Glob(glob) => glob_match(glob, &path.to_string_lossy()),
+            FontMatch::Pattern(pattern) => {
+                let pat = Pattern::new();
+                pattern.patelts.iter().all(|patelt| {
+                    let prop = &patelt.property;
+                    let kind = prop.kind();
+                    let expected = match prop.expr().eval(&kind, &pat, None) {
+                        Some(value) => value,
+                        None => return false,
+                    };
+
+                    font.get(&kind)
+                        .unwrap_or_default()
+                        .iter()
+                        .any(|(value, _)| compare_value_listing(value, &expected))
+                })
+            }
+            FontMatch::Unknown(_) => false,
+        }
+    }
+}
+
+impl FontConfig {
+    /// Return `true` if font file at `path` with `font` pattern is accepted by `<selectfont>` rules
+    ///
+    /// Any `<acceptfont>` match overrides `<rejectfont>`.
+    /// Like fontconfig, globs and patterns are checked separately, so a file rejected by glob is never accepted by pattern.
+    pub fn accept_font(&self, path: &Path, font: &Pattern) -> bool {
+        let glob_ok = self.select_fonts_match(true, path, font);
+        let pattern_ok = self.select_fonts_match(false, path, font);
+
+        glob_ok && pattern_ok
+    }
+
+    fn select_fonts_match(&self, glob: bool, path: &Path, font: &Pattern) -> bool {
+        let matches =
+            |m: &FontMatch| matches!(m, FontMatch::Glob(_)) == glob && m.matches(path, font);
+
+        self.select_fonts
+            .iter()
+            .any(|s| s.accepts.iter().any(matches))
+            || !self
+                .select_fonts
+                .iter()
+                .any(|s| s.rejects.iter().any(matches))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_config_parts, ConfigPart, PropertyKind, Value};
+
+    #[test]
+    fn accept_overrides_reject() {
+        let mut c = FontConfig::default();
+        c.select_fonts.extend(
+            parse_config_parts(
+                r#"<fontconfig>
+    <selectfont>
+        <rejectfont>
+            <glob>/usr/share/fonts/type1/*</glob>
+            <pattern><patelt name="scalable"><bool>false</bool></patelt></pattern>
+        </rejectfont>
+    </selectfont>
+    <selectfont>
+        <acceptfont>
+            <glob>*/Keep?.pfb</glob>
+            <pattern><patelt name="family"><string>Terminus</string></patelt></pattern>
+        </acceptfont>
+    </selectfont>
+</fontconfig>"#,
+            )
+            .unwrap()
+            .into_iter()
+            .filter_map(|p| match p {
+                ConfigPart::SelectFont(s) => Some(s),
+                _ => None,
+            }),
+        );
+
+        let font = |family: &str, scalable: bool| -> Pattern {
+            vec![
+                (PropertyKind::Family, Value::from(family)),
+                (PropertyKind::Scalable, Value::Bool(scalable)),
+            ]
+            .into_iter()
+            .collect()
+        };
+        let type1 = Path::new("/usr/share/fonts/type1/a.pfb");
+        let keep = Path::new("/usr/share/fonts/type1/Keep1.pfb");
+        let other = Path::new("/usr/share/fonts/misc/a.pcf");
+
+        assert!(!c.accept_font(type1, &font("Foo", true)));
+        assert!(c.accept_font(keep, &font("Foo", true)));
+        assert!(c.accept_font(other, &font("Foo", true)));
+        assert!(!c.accept_font(other, &font("Foo", false)));
+        assert!(c.accept_font(other, &font("terminus", false)));
+        assert!(!c.accept_font(type1, &font("Terminus", false)));
+    }
+
+    #[test]
+    fn pattern_listing() {
+        let pattern = |props: &str| {
+            let xml = format!(
+                "<fontconfig><selectfont><acceptfont><pattern>{}</pattern></acceptfont></selectfont></fontconfig>",
+                props
+            );
+            match parse_config_parts(&xml).unwrap().remove(0) {
+                ConfigPart::SelectFont(mut s) => s.accepts.remove(0),
+                _ => unreachable!(),
+            }
+        };
+        let path = Path::new("/usr/share/fonts/a.ttf");
+        let font: Pattern = vec![
+            (PropertyKind::Family, Value::from("DejaVu Sans")),
+            (PropertyKind::Lang, Value::LangSet("en|ko".into())),
+        ]
+        .into_iter()
+        .collect();
+
+        let family = pattern(r#"<patelt name="family"><string>dejavusans</string></patelt>"#);
+        assert!(family.matches(path, &font));
+        let lang = pattern(r#"<patelt name="lang"><string>ko</string></patelt>"#);
+        assert!(lang.matches(path, &font));
+        let lang = pattern(r#"<patelt name="lang"><string>ja</string></patelt>"#);
+        assert!(!lang.matches(path, &font));
+    }
 }
@@ -0,0 +1,21 @@
+use crate::Location;
+
+/// Element which is not modeled by this crate, kept as is
+///
+/// Comments and processing instructions are not kept.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UnknownElement {
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<UnknownNode>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub location: Location,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum UnknownNode {
+    Element(UnknownElement),
+    Text(String),
+}
@@ -1,8 +1,10 @@
-use crate::{Constant, IntOrRange, PropertyKind};
+use crate::{CharSet, Constant, LangResult, LangSet, PropertyKind};
 
 pub type Bool = bool;
-pub type Int = u32;
+pub type Int = i64;
 pub type Double = f64;
+/// Transformation matrix `[xx, xy, yx, yy]`
+pub type Matrix = [Double; 4];
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -40,6 +42,7 @@ pub enum UnaryOp {
 parse_enum! {
     UnaryOp,
     (Not, "not"),
+    (Cecil, "ceil"),
     (Cecil, "cecil"),
     (Floor, "floor"),
     (Round, "round"),
@@ -91,11 +94,14 @@ pub enum Expression {
     Ternary(TernaryOp, Box<[Self; 3]>),
     List(ListOp, Vec<Self>),
     Matrix(Box<[Self; 4]>),
+    /// Expression whose element has attributes not modeled by this crate, evaluated as the expression
+    WithUnknownAttributes(Box<Self>, Vec<(String, String)>),
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PropertyTarget {
+    #[default]
     Default,
     Font,
     Pattern,
@@ -108,14 +114,6 @@ parse_enum! {
     (Pattern, "pattern"),
 }
 
-impl Default for PropertyTarget {
-    fn default() -> Self {
-        PropertyTarget::Default
-    }
-}
-
-pub type CharSet = Vec<IntOrRange>;
-
 /// Runtime typed fontconfig value
 #[derive(Clone, Debug, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -130,16 +128,53 @@ pub enum Value {
     Constant(Constant),
     /// `<bool>false</bool>`
     Bool(Bool),
+    /// Evaluated `<matrix>`
+    Matrix(Matrix),
     /// This element holds the two [`Value::Int`] elements of a range representation.
     Range(Int, Int),
     /// This element holds at least one [`Value::String`] element of a RFC-3066-style languages or more.
-    LangSet(String),
+    LangSet(LangSet),
     /// This element holds at least one [`Value::Int`] element of an Unicode code point or more.
     CharSet(CharSet),
     /// `<name target="font">pixelsize</name>`
     Property(PropertyTarget, PropertyKind),
 }
 
+impl Value {
+    /// Numeric value promoted to [`Double`]
+    pub(crate) fn as_double(&self) -> Option<Double> {
+        match *self {
+            Value::Int(i) => Some(i as Double),
+            Value::Double(d) => Some(d),
+            _ => None,
+        }
+    }
+
+    /// Language set value, a string is promoted to a set of the single language
+    pub(crate) fn as_langset(&self) -> Option<LangSet> {
+        match self {
+            Value::String(s) => Some(std::iter::once(s.as_str()).collect()),
+            Value::LangSet(l) => Some(l.clone()),
+            _ => None,
+        }
+    }
+
+    /// Compare languages of strings or language sets like `FcLangCompare`
+    ///
+    /// Returns `None` if either is neither a string nor a language set
+    pub fn compare_lang(&self, other: &Value) -> Option<LangResult> {
+        Some(self.as_langset()?.compare(&other.as_langset()?))
+    }
+
+    /// Range value, a number is promoted to a single point range
+    pub(crate) fn as_range(&self) -> Option<(Double, Double)> {
+        match *self {
+            Value::Range(b, e) => Some((b as Double, e as Double)),
+            _ => self.as_double().map(|d| (d, d)),
+        }
+    }
+}
+
 macro_rules! from_value {
 	($($name:ident,)+) => {
         $(
@@ -158,6 +193,8 @@ from_value! {
     Double,
     Constant,
     CharSet,
+    LangSet,
+    Matrix,
 }
 
 impl<'a> From<&'a str> for Value {
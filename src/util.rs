@@ -1,52 +1,40 @@
 macro_rules! try_next {
-    ($iter:expr, $($tt:tt)*) => {
+    ($iter:expr, $err:expr) => {
         match $iter.next() {
             Some(e) => e,
-            None => return Err(crate::Error::InvalidFormat(format!($($tt)*))),
+            None => return Err(crate::Error::InvalidFormat($err)),
         }
-    }
+    };
 }
 
 macro_rules! try_text {
     ($node:expr) => {
         match $node.text() {
             Some(t) => t,
-            None => return Err(crate::Error::InvalidFormat("Can't get text".into())),
-        }
-    };
-}
-
-macro_rules! parse_attrs_opt {
-    ($node:expr, { $($key:expr => $lvalue:expr,)+ } $(, { $($str_key:expr => $str_lvalue:expr,)+ } )?) => {
-        for attr in $node.attributes() {
-            match attr.name() {
-                $(
-                    $key => $lvalue = attr.value().parse().ok()?,
-                )+
-                $(
-                    $(
-                        $str_key => $str_lvalue = attr.value().into(),
-                    )+
-                )?
-                _ => {}
-            }
+            None => return Err(crate::Error::InvalidFormat(crate::FormatError::MissingText)),
         }
     };
 }
 
 macro_rules! parse_attrs {
-    ($node:expr, { $($key:expr => $lvalue:expr,)+ } $(, { $($str_key:expr => $str_lvalue:expr,)+ } )?) => {
+    ($node:expr, { $($key:expr => $lvalue:expr,)+ } $(, { $($str_key:expr => $str_lvalue:expr,)+ } )? $(, |$attr:ident| $fallback:expr)?) => {
         for attr in $node.attributes() {
-            match attr.name() {
+            // namespaced attributes are never known ones
+            match (attr.namespace(), attr.name()) {
                 $(
-                    $key => $lvalue = attr.value().parse()?,
+                    (None, $key) => $lvalue = attr.value().parse()?,
                 )+
                 $(
                     $(
-                        $str_key => $str_lvalue = attr.value().into(),
+                        (None, $str_key) => $str_lvalue = attr.value().into(),
                     )+
                 )?
-                _ => {}
+                _ => {
+                    $(
+                        let $attr = attr;
+                        $fallback;
+                    )?
+                }
             }
         }
     };
@@ -88,5 +76,111 @@ macro_rules! parse_enum {
             )+
             |s| Err(crate::Error::ParseEnumError(core::any::type_name::<$ty>(), s.into())),
         }
+
+        /// Display as the name used in config, the first one is used when there are aliases
+        impl core::fmt::Display for $ty {
+            #[allow(unreachable_patterns)]
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                f.write_str(match self {
+                    $(
+                        Self::$variant => $text,
+                    )+
+                })
+            }
+        }
+    };
+}
+
+/// String equality of `FcStrCmpIgnoreBlanksAndCase`
+pub(crate) fn eq_ignore_blanks_and_case(s1: &str, s2: &str) -> bool {
+    let s1 = s1.chars().filter(|c| *c != ' ');
+    let s2 = s2.chars().filter(|c| *c != ' ');
+    s1.map(|c| c.to_ascii_lowercase())
+        .eq(s2.map(|c| c.to_ascii_lowercase()))
+}
+
+/// Shell-style glob matching of `FcStrGlobMatch`
+///
+/// `*` matches any sequence including `/` and `?` matches any single character
+pub(crate) fn glob_match(glob: &str, s: &str) -> bool {
+    let mut glob = glob.chars();
+    let mut s = s.chars();
+
+    while let Some(c) = glob.next() {
+        match c {
+            '*' => {
+                let rest = glob.as_str();
+                if rest.is_empty() {
+                    return true;
+                }
+                loop {
+                    if glob_match(rest, s.as_str()) {
+                        return true;
+                    }
+                    if s.next().is_none() {
+                        return false;
+                    }
+                }
+            }
+            '?' => {
+                if s.next().is_none() {
+                    return false;
+                }
+            }
+            c => {
+                if s.next() != Some(c) {
+                    return false;
+                }
+            }
+        }
+    }
+
+    s.as_str().is_empty()
+}
+
+/// Parse integer like `strtol` with base 0
+///
+/// Surrounding whitespace and sign are allowed, `0x` prefix means hexadecimal and `0` prefix means octal.
+pub(crate) fn parse_int(s: &str) -> crate::Result<crate::Int> {
+    let s = s.trim();
+    let (sign, s) = match s.strip_prefix('-') {
+        Some(s) => ('-', s),
+        None => ('+', s.strip_prefix('+').unwrap_or(s)),
+    };
+    let (radix, digits) = if let Some(hex) = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        (16, hex)
+    } else if s.len() > 1 && s.starts_with('0') {
+        (8, &s[1..])
+    } else {
+        (10, s)
     };
+
+    // explicit sign so another sign in `digits` is an error
+    Ok(crate::Int::from_str_radix(
+        &format!("{}{}", sign, digits),
+        radix,
+    )?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_int_strtol() {
+        let parse = |s| parse_int(s).ok();
+
+        k9::assert_equal!(parse("42"), Some(42));
+        k9::assert_equal!(parse(" -42\n"), Some(-42));
+        k9::assert_equal!(parse("+0x1F"), Some(31));
+        k9::assert_equal!(parse("0X20"), Some(32));
+        k9::assert_equal!(parse("-010"), Some(-8));
+        k9::assert_equal!(parse("0"), Some(0));
+        k9::assert_equal!(parse("08"), None);
+        k9::assert_equal!(parse("0x"), None);
+        k9::assert_equal!(parse("--1"), None);
+        k9::assert_equal!(parse("0x-1"), None);
+        k9::assert_equal!(parse("1a"), None);
+        k9::assert_equal!(parse(""), None);
+    }
 }
@@ -0,0 +1,985 @@
+//! Write config parts back to fontconfig XML
+//!
+//! Output of [`write_config`] is parsed by [`crate::parse_config_parts`] into the same parts.
+
+use crate::*;
+
+const HEADER: &str = r#"<?xml version="1.0"?>
+<!DOCTYPE fontconfig SYSTEM "urn:fontconfig:fonts.dtd">
+"#;
+
+/// Write `parts` as a complete config file with XML declaration and DOCTYPE
+pub fn write_config(parts: &[ConfigPart]) -> String {
+    let mut w = XmlWriter::default();
+
+    w.out.push_str(HEADER);
+    w.start("fontconfig", &[]);
+    for part in parts {
+        part.write_xml(&mut w);
+    }
+    w.end("fontconfig");
+
+    w.out
+}
+
+/// Format config text into canonical form
+///
+/// Output has the same XML declaration and DOCTYPE as [`write_config`], two spaces indentation
+/// and attributes in fixed order with default values omitted.
+/// Comments and processing instructions are kept before the element following them,
+/// ones inside text-only elements are moved after the element.
+///
+/// Element casing is not normalized: fontconfig matches element names case-sensitively,
+/// so `<Match>` is an unknown element and lowercasing it would turn it into a rule.
+/// Unknown elements are written with their names as they are.
+///
+/// Formatting is idempotent, and [`Error::SemanticsChanged`] is returned
+/// when parts of the output aren't the same as ones of `s`.
+pub fn format_config(s: &str) -> Result<String> {
+    let doc = roxmltree::Document::parse_with_options(
+        s,
+        roxmltree::ParsingOptions {
+            allow_dtd: true,
+            ..Default::default()
+        },
+    )?;
+    let parts = crate::parser::parse_config(
+        &doc,
+        None,
+        &ParseOptions::default(),
+        &mut Diagnostics::new(),
+    )?
+    .collect::<Result<Vec<_>>>()?;
+
+    let mut w = XmlWriter::default();
+    collect_misc(doc.root(), &mut Vec::new(), &mut w.misc);
+
+    w.out.push_str(HEADER);
+    w.start("fontconfig", &[]);
+    for part in parts.iter() {
+        part.write_xml(&mut w);
+    }
+    w.end("fontconfig");
+    w.flush_misc(|_| true);
+
+    if parse_config_parts(&w.out)? != parts {
+        return Err(Error::SemanticsChanged);
+    }
+
+    Ok(w.out)
+}
+
+impl FontConfig {
+    /// Convert merged config into parts of a single config file
+    ///
+    /// Parts are taken from [`Self::rules`] in evaluation order.
+    /// Directories are written as resolved absolute paths,
+    /// descriptions and unknown elements are dropped.
+    pub fn to_config_parts(&self) -> Vec<ConfigPart> {
+        self.rules
+            .iter()
+            .filter_map(|rule| {
+                let part = match &rule.part {
+                    ConfigPart::Description(_)
+                    | ConfigPart::Include(_)
+                    | ConfigPart::Unknown(_) => return None,
+                    ConfigPart::Dir(dir) => ConfigPart::Dir(Dir {
+                        prefix: DirPrefix::Default,
+                        path: dir.calculate_path(&rule.file).to_string_lossy().into(),
+                        ..dir.clone()
+                    }),
+                    ConfigPart::CacheDir(dir) => ConfigPart::CacheDir(CacheDir {
+                        prefix: DirPrefix::Default,
+                        path: dir.calculate_path(&rule.file).to_string_lossy().into(),
+                        ..dir.clone()
+                    }),
+                    ConfigPart::RemapDir(dir) => ConfigPart::RemapDir(RemapDir {
+                        prefix: DirPrefix::Default,
+                        path: dir.calculate_path(&rule.file).to_string_lossy().into(),
+                        ..dir.clone()
+                    }),
+                    part => part.clone(),
+                };
+
+                Some(part)
+            })
+            .collect()
+    }
+
+    /// Write merged config as a single config file
+    pub fn to_xml(&self) -> String {
+        write_config(&self.to_config_parts())
+    }
+}
+
+/// Comments and processing instructions under `node` with their position
+///
+/// Position is the element indices of ancestors followed by the number of preceding sibling elements.
+fn collect_misc(node: roxmltree::Node, path: &mut Vec<usize>, out: &mut Vec<(Vec<usize>, String)>) {
+    path.push(0);
+    for child in node.children() {
+        if child.is_element() {
+            collect_misc(child, path, out);
+            *path.last_mut().unwrap() += 1;
+        } else if child.is_comment() {
+            let text = format!("<!--{}-->", child.text().unwrap_or_default());
+            out.push((path.clone(), text));
+        } else if let Some(pi) = child.pi() {
+            let text = match pi.value {
+                Some(value) => format!("<?{} {}?>", pi.target, value),
+                None => format!("<?{}?>", pi.target),
+            };
+            out.push((path.clone(), text));
+        }
+    }
+    path.pop();
+}
+
+struct XmlWriter {
+    out: String,
+    depth: usize,
+    /// Position of the next element, see [`collect_misc`]
+    path: Vec<usize>,
+    /// Comments and processing instructions not written yet
+    misc: Vec<(Vec<usize>, String)>,
+    /// Attributes added to the next element, see [`Expression::WithUnknownAttributes`]
+    pending_attributes: Vec<(String, String)>,
+}
+
+impl Default for XmlWriter {
+    fn default() -> Self {
+        Self {
+            out: String::new(),
+            depth: 0,
+            path: vec![0],
+            misc: Vec::new(),
+            pending_attributes: Vec::new(),
+        }
+    }
+}
+
+impl XmlWriter {
+    /// Write pending comments and processing instructions at position matching `f` in order
+    fn flush_misc(&mut self, f: impl Fn(&[usize]) -> bool) {
+        let (flush, rest) = std::mem::take(&mut self.misc)
+            .into_iter()
+            .partition::<Vec<_>, _>(|(path, _)| f(path));
+        self.misc = rest;
+
+        for (_, text) in flush {
+            self.indent();
+            self.out.push_str(&text);
+            self.out.push('\n');
+        }
+    }
+
+    fn before_element(&mut self) {
+        if !self.misc.is_empty() {
+            let path = self.path.clone();
+            self.flush_misc(|p| p == &path[..]);
+        }
+    }
+
+    /// Write what was inside of the element at current position, and move to the next one
+    fn after_element(&mut self) {
+        if !self.misc.is_empty() {
+            let path = self.path.clone();
+            self.flush_misc(|p| p.len() > path.len() && p.starts_with(&path));
+        }
+        if let Some(last) = self.path.last_mut() {
+            *last += 1;
+        }
+    }
+
+    fn indent(&mut self) {
+        for _ in 0..self.depth {
+            self.out.push_str("  ");
+        }
+    }
+
+    fn open_tag(&mut self, name: &str, attrs: &[(&str, &str)]) {
+        let pending = std::mem::take(&mut self.pending_attributes);
+
+        self.out.push('<');
+        self.out.push_str(name);
+        for (key, value) in attrs
+            .iter()
+            .copied()
+            .chain(pending.iter().map(|(k, v)| (k.as_str(), v.as_str())))
+        {
+            self.out.push(' ');
+            self.out.push_str(key);
+            self.out.push_str("=\"");
+            escape(&mut self.out, value);
+            self.out.push('"');
+        }
+    }
+
+    /// Start element which has element children
+    fn start(&mut self, name: &str, attrs: &[(&str, &str)]) {
+        self.before_element();
+        self.indent();
+        self.open_tag(name, attrs);
+        self.out.push_str(">\n");
+        self.depth += 1;
+        self.path.push(0);
+    }
+
+    fn end(&mut self, name: &str) {
+        self.path.pop();
+        self.after_element();
+        self.depth -= 1;
+        self.indent();
+        self.out.push_str("</");
+        self.out.push_str(name);
+        self.out.push_str(">\n");
+    }
+
+    fn empty(&mut self, name: &str, attrs: &[(&str, &str)]) {
+        self.before_element();
+        self.indent();
+        self.open_tag(name, attrs);
+        self.out.push_str(" />\n");
+        self.after_element();
+    }
+
+    /// Element which only has text
+    fn text(&mut self, name: &str, attrs: &[(&str, &str)], text: &str) {
+        self.before_element();
+        self.indent();
+        self.open_tag(name, attrs);
+        self.out.push('>');
+        escape(&mut self.out, text);
+        self.out.push_str("</");
+        self.out.push_str(name);
+        self.out.push_str(">\n");
+        self.after_element();
+    }
+
+    /// Write element without adding any whitespace, so text content is kept as is
+    fn raw(&mut self, e: &UnknownElement) {
+        let attrs: Vec<_> = e
+            .attributes
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .collect();
+
+        self.open_tag(&e.name, &attrs);
+        if e.children.is_empty() {
+            self.out.push_str(" />");
+            return;
+        }
+        self.out.push('>');
+        for child in e.children.iter() {
+            match child {
+                UnknownNode::Element(e) => self.raw(e),
+                UnknownNode::Text(text) => escape(&mut self.out, text),
+            }
+        }
+        self.out.push_str("</");
+        self.out.push_str(&e.name);
+        self.out.push('>');
+    }
+
+    fn unknown(&mut self, e: &UnknownElement) {
+        self.before_element();
+        self.indent();
+        self.raw(e);
+        self.out.push('\n');
+        self.after_element();
+    }
+}
+
+fn escape(out: &mut String, s: &str) {
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+}
+
+trait WriteXml {
+    fn write_xml(&self, w: &mut XmlWriter);
+}
+
+macro_rules! impl_to_xml {
+    ($($ty:ty,)+) => {
+        $(
+            impl $ty {
+                /// Write as fontconfig XML element
+                pub fn to_xml(&self) -> String {
+                    let mut w = XmlWriter::default();
+                    self.write_xml(&mut w);
+                    w.out
+                }
+            }
+        )+
+    };
+}
+
+impl_to_xml! {
+    ConfigPart,
+    Alias,
+    Dir,
+    CacheDir,
+    Include,
+    RemapDir,
+    Config,
+    SelectFont,
+    Match,
+    Test,
+    Edit,
+    Expression,
+    UnknownElement,
+}
+
+impl WriteXml for ConfigPart {
+    fn write_xml(&self, w: &mut XmlWriter) {
+        match self {
+            ConfigPart::Description(desc) => w.text(
+                "description",
+                &with_unknown(Vec::new(), &desc.unknown_attributes),
+                &desc.text,
+            ),
+            ConfigPart::SelectFont(s) => s.write_xml(w),
+            ConfigPart::Dir(dir) => dir.write_xml(w),
+            ConfigPart::CacheDir(dir) => dir.write_xml(w),
+            ConfigPart::Include(include) => include.write_xml(w),
+            ConfigPart::Match(m) => m.write_xml(w),
+            ConfigPart::Config(c) => c.write_xml(w),
+            ConfigPart::Alias(alias) => alias.write_xml(w),
+            ConfigPart::RemapDir(dir) => dir.write_xml(w),
+            ConfigPart::ResetDirs => w.empty("reset-dirs", &[]),
+            ConfigPart::Unknown(e) => e.write_xml(w),
+        }
+    }
+}
+
+impl WriteXml for Alias {
+    fn write_xml(&self, w: &mut XmlWriter) {
+        let binding =
+            Some(self.binding.to_string()).filter(|_| self.binding != EditBinding::default());
+        let attrs = with_unknown(
+            attrs(&[("binding", binding.as_deref())]),
+            &self.unknown_attributes,
+        );
+        w.start("alias", &attrs);
+        w.text("family", &[], &self.alias);
+        for (name, families, unknown) in [
+            ("prefer", &self.prefer, &self.prefer_unknown),
+            ("accept", &self.accept, &self.accept_unknown),
+            ("default", &self.default, &self.default_unknown),
+        ] {
+            if families.is_empty() && unknown.is_empty() {
+                continue;
+            }
+            w.start(name, &[]);
+            for family in families.iter() {
+                w.text("family", &[], family);
+            }
+            for e in unknown.iter() {
+                w.unknown(e);
+            }
+            w.end(name);
+        }
+        for e in self.unknown.iter() {
+            w.unknown(e);
+        }
+        w.end("alias");
+    }
+}
+
+fn prefix_attr(prefix: DirPrefix) -> Option<String> {
+    match prefix {
+        DirPrefix::Default => None,
+        prefix => Some(prefix.to_string()),
+    }
+}
+
+/// Attributes skipping ones with `None`
+fn attrs<'a>(attrs: &[(&'a str, Option<&'a str>)]) -> Vec<(&'a str, &'a str)> {
+    attrs
+        .iter()
+        .filter_map(|(key, value)| value.map(|value| (*key, value)))
+        .collect()
+}
+
+/// Known `attrs` followed by attributes not modeled by this crate
+fn with_unknown<'a>(
+    mut attrs: Vec<(&'a str, &'a str)>,
+    unknown_attributes: &'a [(String, String)],
+) -> Vec<(&'a str, &'a str)> {
+    attrs.extend(
+        unknown_attributes
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str())),
+    );
+    attrs
+}
+
+fn non_empty(s: &str) -> Option<&str> {
+    Some(s).filter(|s| !s.is_empty())
+}
+
+impl WriteXml for Dir {
+    fn write_xml(&self, w: &mut XmlWriter) {
+        let prefix = prefix_attr(self.prefix);
+        let attrs = with_unknown(
+            attrs(&[
+                ("prefix", prefix.as_deref()),
+                ("salt", non_empty(&self.salt)),
+            ]),
+            &self.unknown_attributes,
+        );
+        w.text("dir", &attrs, &self.path);
+    }
+}
+
+impl WriteXml for CacheDir {
+    fn write_xml(&self, w: &mut XmlWriter) {
+        let prefix = prefix_attr(self.prefix);
+        let attrs = with_unknown(
+            attrs(&[("prefix", prefix.as_deref())]),
+            &self.unknown_attributes,
+        );
+        w.text("cachedir", &attrs, &self.path);
+    }
+}
+
+impl WriteXml for Include {
+    fn write_xml(&self, w: &mut XmlWriter) {
+        let prefix = prefix_attr(self.prefix);
+        let ignore_missing = Some("yes").filter(|_| self.ignore_missing);
+        let attrs = with_unknown(
+            attrs(&[
+                ("prefix", prefix.as_deref()),
+                ("ignore_missing", ignore_missing),
+            ]),
+            &self.unknown_attributes,
+        );
+        w.text("include", &attrs, &self.path);
+    }
+}
+
+impl WriteXml for RemapDir {
+    fn write_xml(&self, w: &mut XmlWriter) {
+        let prefix = prefix_attr(self.prefix);
+        let attrs = with_unknown(
+            attrs(&[
+                ("prefix", prefix.as_deref()),
+                ("as-path", non_empty(&self.as_path)),
+                ("salt", non_empty(&self.salt)),
+            ]),
+            &self.unknown_attributes,
+        );
+        w.text("remap-dir", &attrs, &self.path);
+    }
+}
+
+impl WriteXml for Config {
+    fn write_xml(&self, w: &mut XmlWriter) {
+        w.start("config", &[]);
+        // only the first child of `<rescan>` is its value, so each value has its own element
+        for blank in self.blanks.iter() {
+            w.start("blank", &[]);
+            blank.write_xml(w);
+            w.end("blank");
+        }
+        if !self.blank_unknown.is_empty() {
+            w.start("blank", &[]);
+            for e in self.blank_unknown.iter() {
+                w.unknown(e);
+            }
+            w.end("blank");
+        }
+        for (i, rescan) in self.rescans.iter().enumerate() {
+            w.start("rescan", &[]);
+            w.text("int", &[], &rescan.to_string());
+            // after the `<int>`, so the first child is still the value when parsed again
+            if i + 1 == self.rescans.len() {
+                for e in self.rescan_unknown.iter() {
+                    w.unknown(e);
+                }
+            }
+            w.end("rescan");
+        }
+        if self.rescans.is_empty() && !self.rescan_unknown.is_empty() {
+            w.start("rescan", &[]);
+            for e in self.rescan_unknown.iter() {
+                w.unknown(e);
+            }
+            w.end("rescan");
+        }
+        for e in self.unknown.iter() {
+            w.unknown(e);
+        }
+        w.end("config");
+    }
+}
+
+impl WriteXml for IntOrRange {
+    fn write_xml(&self, w: &mut XmlWriter) {
+        match self {
+            IntOrRange::Int(i) => w.text("int", &[], &i.to_string()),
+            IntOrRange::Range(begin, end) => write_range(w, *begin, *end),
+        }
+    }
+}
+
+fn write_range(w: &mut XmlWriter, begin: Int, end: Int) {
+    w.start("range", &[]);
+    w.text("int", &[], &begin.to_string());
+    w.text("int", &[], &end.to_string());
+    w.end("range");
+}
+
+impl WriteXml for SelectFont {
+    fn write_xml(&self, w: &mut XmlWriter) {
+        w.start("selectfont", &[]);
+        for (name, matches) in [("rejectfont", &self.rejects), ("acceptfont", &self.accepts)] {
+            if matches.is_empty() {
+                continue;
+            }
+            w.start(name, &[]);
+            for m in matches.iter() {
+                match m {
+                    FontMatch::Glob(glob) => w.text("glob", &[], glob),
+                    FontMatch::Pattern(pattern) => {
+                        w.start("pattern", &[]);
+                        for patelt in pattern.patelts.iter() {
+                            let name = patelt.property.kind().to_string();
+                            let attrs =
+                                with_unknown(vec![("name", &name)], &patelt.unknown_attributes);
+                            w.start("patelt", &attrs);
+                            patelt.property.expr().write_xml(w);
+                            w.end("patelt");
+                        }
+                        for e in pattern.unknown.iter() {
+                            w.unknown(e);
+                        }
+                        w.end("pattern");
+                    }
+                    FontMatch::Unknown(e) => w.unknown(e),
+                }
+            }
+            w.end(name);
+        }
+        for e in self.unknown.iter() {
+            w.unknown(e);
+        }
+        w.end("selectfont");
+    }
+}
+
+impl WriteXml for Match {
+    fn write_xml(&self, w: &mut XmlWriter) {
+        let target =
+            Some(self.target.to_string()).filter(|_| self.target != MatchTarget::default());
+        let attrs = with_unknown(
+            attrs(&[("target", target.as_deref())]),
+            &self.unknown_attributes,
+        );
+        w.start("match", &attrs);
+        for test in self.tests.iter() {
+            test.write_xml(w);
+        }
+        for edit in self.edits.iter() {
+            edit.write_xml(w);
+        }
+        for e in self.unknown.iter() {
+            w.unknown(e);
+        }
+        w.end("match");
+    }
+}
+
+/// Write `<test>` or `<edit>` whose known attributes are `attrs`
+fn write_property_element(
+    w: &mut XmlWriter,
+    name: &str,
+    attrs: &[(&str, &str)],
+    value: &Property,
+    extra_values: &[Expression],
+    unknown_attributes: &[(String, String)],
+    unknown: &[UnknownElement],
+) {
+    let kind = value.kind().to_string();
+    let mut known = vec![("name", kind.as_str())];
+    known.extend_from_slice(attrs);
+
+    w.start(name, &with_unknown(known, unknown_attributes));
+    value.expr().write_xml(w);
+    for expr in extra_values {
+        expr.write_xml(w);
+    }
+    for e in unknown {
+        w.unknown(e);
+    }
+    w.end(name);
+}
+
+impl WriteXml for Test {
+    fn write_xml(&self, w: &mut XmlWriter) {
+        let qual = self.qual.to_string();
+        let target = self.target.to_string();
+        let compare = self.compare.to_string();
+        let mut attrs = Vec::new();
+
+        if self.qual != TestQual::default() {
+            attrs.push(("qual", qual.as_str()));
+        }
+        if self.target != TestTarget::default() {
+            attrs.push(("target", target.as_str()));
+        }
+        if self.compare != TestCompare::default() {
+            attrs.push(("compare", compare.as_str()));
+        }
+        if self.ignore_blanks {
+            attrs.push(("ignore-blanks", "true"));
+        }
+
+        write_property_element(
+            w,
+            "test",
+            &attrs,
+            &self.value,
+            &self.extra_values,
+            &self.unknown_attributes,
+            &self.unknown,
+        );
+    }
+}
+
+impl WriteXml for Edit {
+    fn write_xml(&self, w: &mut XmlWriter) {
+        let mode = self.mode.to_string();
+        let binding = self.binding.to_string();
+        let mut attrs = Vec::new();
+
+        if self.mode != EditMode::default() {
+            attrs.push(("mode", mode.as_str()));
+        }
+        if self.binding != EditBinding::default() {
+            attrs.push(("binding", binding.as_str()));
+        }
+
+        write_property_element(
+            w,
+            "edit",
+            &attrs,
+            &self.value,
+            &self.extra_values,
+            &self.unknown_attributes,
+            &self.unknown,
+        );
+    }
+}
+
+impl WriteXml for Expression {
+    fn write_xml(&self, w: &mut XmlWriter) {
+        match self {
+            Expression::Simple(value) => value.write_xml(w),
+            Expression::Unary(op, expr) => {
+                let name = op.to_string();
+                w.start(&name, &[]);
+                expr.write_xml(w);
+                w.end(&name);
+            }
+            Expression::Binary(op, exprs) => write_op(w, &op.to_string(), exprs.iter()),
+            Expression::Ternary(op, exprs) => write_op(w, &op.to_string(), exprs.iter()),
+            Expression::List(op, exprs) => write_op(w, &op.to_string(), exprs.iter()),
+            Expression::Matrix(exprs) => write_op(w, "matrix", exprs.iter()),
+            Expression::WithUnknownAttributes(expr, attrs) => {
+                w.pending_attributes.extend(attrs.iter().cloned());
+                expr.write_xml(w);
+            }
+        }
+    }
+}
+
+fn write_op<'a>(w: &mut XmlWriter, name: &str, exprs: impl Iterator<Item = &'a Expression>) {
+    w.start(name, &[]);
+    for expr in exprs {
+        expr.write_xml(w);
+    }
+    w.end(name);
+}
+
+impl WriteXml for Value {
+    fn write_xml(&self, w: &mut XmlWriter) {
+        match self {
+            Value::Int(i) => w.text("int", &[], &i.to_string()),
+            Value::Double(d) => w.text("double", &[], &d.to_string()),
+            Value::String(s) => w.text("string", &[], s),
+            Value::Constant(c) => w.text("const", &[], &c.to_string()),
+            Value::Bool(b) => w.text("bool", &[], &b.to_string()),
+            Value::Matrix(m) => {
+                w.start("matrix", &[]);
+                for d in m.iter() {
+                    w.text("double", &[], &d.to_string());
+                }
+                w.end("matrix");
+            }
+            Value::Range(begin, end) => write_range(w, *begin, *end),
+            Value::LangSet(langset) => {
+                w.start("langset", &[]);
+                for lang in langset.iter() {
+                    w.text("string", &[], lang);
+                }
+                w.end("langset");
+            }
+            Value::CharSet(charset) => {
+                w.start("charset", &[]);
+                for c in charset.int_or_ranges() {
+                    c.write_xml(w);
+                }
+                w.end("charset");
+            }
+            Value::Property(target, kind) => {
+                let target = target.to_string();
+                let attrs = attrs(&[("target", Some(target.as_str()).filter(|t| *t != "default"))]);
+                w.text("name", &attrs, &kind.to_string());
+            }
+        }
+    }
+}
+
+impl WriteXml for UnknownElement {
+    fn write_xml(&self, w: &mut XmlWriter) {
+        w.unknown(self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unknown(name: &str) -> UnknownElement {
+        UnknownElement {
+            name: name.into(),
+            attributes: vec![("a".into(), "1".into())],
+            children: vec![UnknownNode::Text("text".into())],
+            ..Default::default()
+        }
+    }
+
+    fn unknown_attr(name: &str, value: &str) -> Vec<(String, String)> {
+        vec![(name.into(), value.into())]
+    }
+
+    #[test]
+    fn write_roundtrip() {
+        let parts = vec![
+            ConfigPart::Description(Description {
+                text: "a & b <c>".into(),
+                unknown_attributes: unknown_attr("domain", "fontconfig-conf"),
+            }),
+            ConfigPart::Dir(Dir {
+                prefix: DirPrefix::Xdg,
+                salt: "s".into(),
+                path: "fonts".into(),
+                unknown_attributes: unknown_attr("x-dir", "a"),
+                ..Default::default()
+            }),
+            ConfigPart::CacheDir(CacheDir {
+                path: "cache".into(),
+                unknown_attributes: unknown_attr("x-cachedir", "a"),
+                ..Default::default()
+            }),
+            ConfigPart::ResetDirs,
+            ConfigPart::Alias(Alias {
+                alias: "Arial".into(),
+                default: vec!["Helvetica".into()],
+                binding: EditBinding::Same,
+                unknown_attributes: unknown_attr("x-alias", "a"),
+                unknown: vec![unknown("x-family")],
+                ..Default::default()
+            }),
+            ConfigPart::Include(Include {
+                prefix: DirPrefix::Relative,
+                ignore_missing: true,
+                path: "conf.d".into(),
+                unknown_attributes: unknown_attr("deprecated", "yes"),
+                ..Default::default()
+            }),
+            ConfigPart::RemapDir(RemapDir {
+                as_path: "/fonts".into(),
+                path: "/host/fonts".into(),
+                unknown_attributes: unknown_attr("x-remap-dir", "a"),
+                ..Default::default()
+            }),
+            ConfigPart::Config(Config {
+                blanks: vec![IntOrRange::Int(0), IntOrRange::Range(1, 5)],
+                rescans: vec![30],
+                unknown: vec![unknown("x-config")],
+                ..Default::default()
+            }),
+            ConfigPart::SelectFont(SelectFont {
+                rejects: vec![FontMatch::Glob("*.pcf".into())],
+                unknown: vec![unknown("x-selectfont")],
+                ..Default::default()
+            }),
+            ConfigPart::Match(Match {
+                target: MatchTarget::Font,
+                unknown_attributes: vec![
+                    ("x-match".into(), "a".into()),
+                    ("x:match".into(), "b".into()),
+                    ("xmlns:x".into(), "urn:x".into()),
+                ],
+                tests: vec![Test {
+                    qual: TestQual::All,
+                    target: TestTarget::Pattern,
+                    compare: TestCompare::NotContains,
+                    value: Property::Lang(Value::LangSet("ko".into()).into()),
+                    extra_values: vec![Value::LangSet("ja".into()).into()],
+                    ..Default::default()
+                }],
+                edits: vec![Edit {
+                    mode: EditMode::AppendLast,
+                    binding: EditBinding::Strong,
+                    value: Property::Matrix(Expression::List(
+                        ListOp::Times,
+                        vec![
+                            Value::Property(PropertyTarget::Font, PropertyKind::Matrix).into(),
+                            Expression::Matrix(Box::new([
+                                Value::Double(1.5).into(),
+                                Value::Double(0.0).into(),
+                                Expression::Unary(UnaryOp::Cecil, Box::new(Value::Int(1).into())),
+                                Value::Constant(Constant::Bold).into(),
+                            ])),
+                        ],
+                    )),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }),
+            ConfigPart::Match(Match {
+                edits: vec![
+                    Edit {
+                        value: Property::Family(Value::from("").into()),
+                        ..Default::default()
+                    },
+                    Edit {
+                        value: Property::Matrix(Value::Matrix([1.0, 0.2, 0.0, 1.0]).into()),
+                        ..Default::default()
+                    },
+                ],
+                ..Default::default()
+            }),
+        ];
+
+        let xml = write_config(&parts);
+        assert!(xml.starts_with(HEADER));
+        k9::assert_equal!(parse_config_parts(&xml).unwrap(), parts);
+    }
+
+    #[test]
+    fn write_roundtrip_unknown() {
+        let source = r#"<fontconfig>
+  <alias>
+    <family>a</family>
+    <prefer><family>b</family><x-prefer /></prefer>
+    <accept><x-accept /></accept>
+    <default><family>c</family><x-default>d</x-default></default>
+  </alias>
+  <selectfont>
+    <acceptfont>
+      <glob>*.ttf</glob>
+      <x-acceptfont />
+      <pattern>
+        <patelt name="family" x-patelt="1"><string x-string="1">a</string></patelt>
+        <x-pattern />
+      </pattern>
+    </acceptfont>
+    <rejectfont><x-rejectfont /></rejectfont>
+  </selectfont>
+  <config>
+    <rescan><int>30</int><x-rescan /></rescan>
+    <blank><int>1</int><range><int>2</int><int>3</int></range><x-blank /></blank>
+  </config>
+  <match>
+    <test name="family"><name target="font" x-name="1">family</name></test>
+    <edit name="pixelsize"><double x-double="1">1</double></edit>
+  </match>
+</fontconfig>"#;
+        let parts = parse_config_parts(source).unwrap();
+        let xml = write_config(&parts);
+
+        for unknown in [
+            "<x-prefer />",
+            "<x-accept />",
+            "<x-default>d</x-default>",
+            "<x-acceptfont />",
+            "x-patelt=\"1\"",
+            "x-string=\"1\"",
+            "<x-pattern />",
+            "<x-rejectfont />",
+            "<x-rescan />",
+            "<x-blank />",
+            "x-name=\"1\"",
+            "x-double=\"1\"",
+        ] {
+            assert!(xml.contains(unknown), "{} is not in {}", unknown, xml);
+        }
+        k9::assert_equal!(parse_config_parts(&xml).unwrap(), parts);
+    }
+
+    #[test]
+    fn format_canonical() {
+        let source = r#"<?xml version="1.0"?>
+<!-- before root -->
+<fontconfig>
+<!-- keep -->
+        <match target="pattern"><test compare="eq" name="family" qual="any"><string>a</string></test>
+  <!-- inside match -->
+    <edit binding="weak" name="family" mode="assign"><string>b<!-- in text --></string></edit>
+  <!-- end of match --></match>
+</fontconfig>
+<!-- after root -->"#;
+        let formatted = format_config(source).unwrap();
+
+        k9::assert_equal!(
+            formatted,
+            format!(
+                "{}{}",
+                HEADER,
+                r#"<!-- before root -->
+<fontconfig>
+  <!-- keep -->
+  <match>
+    <test name="family">
+      <string>a</string>
+    </test>
+    <!-- inside match -->
+    <edit name="family">
+      <string>b</string>
+      <!-- in text -->
+    </edit>
+    <!-- end of match -->
+  </match>
+</fontconfig>
+<!-- after root -->
+"#
+            )
+        );
+        k9::assert_equal!(format_config(&formatted).unwrap(), formatted);
+    }
+
+    #[test]
+    fn format_keeps_casing() {
+        let formatted =
+            format_config("<fontconfig><Dir>/a</Dir><dir>/b</dir></fontconfig>").unwrap();
+
+        k9::assert_equal!(
+            formatted,
+            format!(
+                "{}{}",
+                HEADER, "<fontconfig>\n  <Dir>/a</Dir>\n  <dir>/b</dir>\n</fontconfig>\n"
+            )
+        );
+    }
+}
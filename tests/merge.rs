@@ -1,4 +1,5 @@
 use fontconfig_parser::*;
+use std::path::Path;
 
 #[test]
 fn merge_full() {
@@ -9,22 +10,26 @@ fn merge_full() {
     assert!(!c.dirs.contains(&DirData {
         path: "/before/reset".into(),
         salt: "".into(),
+        ..Default::default()
     }));
     assert!(c.dirs.contains(&DirData {
         path: "/after/reset".into(),
         salt: "".into(),
+        ..Default::default()
     }));
 
     // 00-6_reset-dirs-lex-order.conf
     assert!(c.dirs.contains(&DirData {
         path: "/after/after/reset".into(),
         salt: "".into(),
+        ..Default::default()
     }));
 
     // 00-nixos-cache.conf
     assert!(c.dirs.contains(&DirData {
         path: "/nix/store/i1yhgnfvaihqzs079lcx4zjnrdzcvaak-noto-fonts-2020-01-23".into(),
         salt: "".into(),
+        ..Default::default()
     }));
 
     // 69-unifont.yaml
@@ -33,5 +38,269 @@ fn merge_full() {
         prefer: vec!["FreeSerif".into(), "Code2000".into(), "Code2001".into(),],
         accept: vec![],
         default: vec![],
+        ..Default::default()
     }));
+
+    // 30-metric-aliases.conf
+    assert!(c.aliases.contains(&Alias {
+        alias: "Liberation Sans".into(),
+        default: vec!["Arial".into()],
+        binding: EditBinding::Same,
+        ..Default::default()
+    }));
+}
+
+#[test]
+fn merge_lower_aliases() {
+    let mut c = FontConfig {
+        lower_aliases: true,
+        ..Default::default()
+    };
+    c.merge_config("./test-conf/fonts.conf").unwrap();
+
+    assert!(c.aliases.is_empty());
+    assert!(c.matches.contains(
+        &Alias {
+            alias: "serif".into(),
+            prefer: vec!["FreeSerif".into(), "Code2000".into(), "Code2001".into(),],
+            accept: vec![],
+            default: vec![],
+            ..Default::default()
+        }
+        .into()
+    ));
+}
+
+#[test]
+fn merge_rules_order() {
+    let mut c = FontConfig::default();
+    c.merge_config("./test-conf/fonts.conf").unwrap();
+
+    let reset: Vec<_> = c
+        .rules
+        .iter()
+        .filter(|r| r.file.ends_with("00-5_reset-dirs.conf"))
+        .map(|r| (r.index, r.part.clone()))
+        .collect();
+
+    k9::assert_equal!(
+        reset,
+        vec![
+            (
+                0,
+                ConfigPart::Dir(Dir {
+                    path: "/before/reset".into(),
+                    ..Default::default()
+                })
+            ),
+            (1, ConfigPart::ResetDirs),
+            (
+                2,
+                ConfigPart::Dir(Dir {
+                    path: "/after/reset".into(),
+                    ..Default::default()
+                })
+            ),
+        ]
+    );
+
+    // conf.d is included in lexicographic order
+    let files: Vec<_> = c.rules.iter().map(|r| r.file.clone()).collect();
+    let pos = |name: &str| files.iter().position(|f| f.ends_with(name)).unwrap();
+    assert!(pos("00-5_reset-dirs.conf") < pos("10-hinting-slight.conf"));
+    assert_eq!(
+        c.rules
+            .iter()
+            .filter(|r| matches!(r.part, ConfigPart::Match(_)))
+            .count(),
+        c.matches.len()
+    );
+}
+
+#[test]
+fn merge_location() {
+    let mut c = FontConfig::default();
+    c.merge_config("./test-conf/fonts.conf").unwrap();
+
+    let dir = c
+        .dirs
+        .iter()
+        .find(|d| d.path == Path::new("/after/reset"))
+        .unwrap();
+
+    assert!(dir
+        .location
+        .file
+        .as_ref()
+        .unwrap()
+        .ends_with("conf.d/00-5_reset-dirs.conf"));
+    assert_eq!((dir.location.line, dir.location.column), (10, 3));
+}
+
+#[test]
+fn merge_error_location() {
+    let dir = std::env::temp_dir().join(format!("fontconfig-parser-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("broken.conf");
+    std::fs::write(
+        &path,
+        "<fontconfig>\n  <config><rescan><int>1x</int></rescan></config>\n</fontconfig>\n",
+    )
+    .unwrap();
+
+    let mut c = FontConfig::default();
+    let err = c.merge_config(&path).unwrap_err();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let location = err.location().unwrap();
+    assert_eq!(location.file.as_deref(), Some(path.as_path()));
+    assert_eq!((location.line, location.column), (2, 19));
+    assert!(matches!(err.kind(), Error::ParseIntError(_)));
+}
+
+#[test]
+fn merge_diagnostics() {
+    let dir = std::env::temp_dir().join(format!(
+        "fontconfig-parser-diagnostics-{}",
+        std::process::id()
+    ));
+    std::fs::create_dir_all(dir.join("conf.d")).unwrap();
+    let path = dir.join("fonts.conf");
+    std::fs::write(
+        &path,
+        r#"<fontconfig>
+  <include prefix="relative">missing.conf</include>
+  <include prefix="relative" ignore_missing="yes">missing.conf</include>
+  <include prefix="relative">conf.d</include>
+  <selectfont>
+    <rejectfont>
+      <pattern>
+        <patelt name="pixelsize"><int>x</int></patelt>
+        <patelt name="scalable"><bool>false</bool></patelt>
+      </pattern>
+    </rejectfont>
+  </selectfont>
+</fontconfig>
+"#,
+    )
+    .unwrap();
+    std::fs::write(dir.join("conf.d/10-broken.conf"), "<fontconfig>").unwrap();
+    std::fs::write(
+        dir.join("conf.d/20-ok.conf"),
+        "<fontconfig><dir>/ok</dir></fontconfig>",
+    )
+    .unwrap();
+
+    let mut c = FontConfig::default();
+    let mut diagnostics = Diagnostics::new();
+    let ret = c.merge_config_with_diagnostics(&path, &mut diagnostics);
+    std::fs::remove_dir_all(&dir).unwrap();
+    ret.unwrap();
+
+    let found: Vec<_> = diagnostics
+        .iter()
+        .map(|d| {
+            (
+                d.severity,
+                d.location
+                    .file
+                    .as_ref()
+                    .unwrap()
+                    .file_name()
+                    .unwrap()
+                    .to_owned(),
+                d.location.line,
+            )
+        })
+        .collect();
+
+    k9::assert_equal!(
+        found,
+        vec![
+            (Severity::Warning, "fonts.conf".into(), 8),
+            (Severity::Error, "fonts.conf".into(), 2),
+            (Severity::Error, "10-broken.conf".into(), 1),
+        ]
+    );
+    assert!(matches!(
+        diagnostics.iter().last().unwrap().error.kind(),
+        Error::Xml(_)
+    ));
+    assert!(diagnostics.has_errors());
+
+    // valid parts are still merged
+    assert!(c.dirs.iter().any(|d| d.path == Path::new("/ok")));
+    match &c.select_fonts[0].rejects[0] {
+        FontMatch::Pattern(pattern) => {
+            assert_eq!(pattern.patelts.len(), 1);
+            // invalid `<patelt>` is kept as is
+            assert_eq!(pattern.unknown[0].name, "patelt");
+        }
+        m => panic!("Unexpected font match {:?}", m),
+    }
+}
+
+#[test]
+fn merge_ignore_missing() {
+    let dir = std::env::temp_dir().join(format!(
+        "fontconfig-parser-ignore-missing-{}",
+        std::process::id()
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("fonts.conf");
+    std::fs::write(
+        &path,
+        r#"<fontconfig>
+  <include prefix="relative" ignore_missing="yes">missing.conf</include>
+  <include prefix="relative" ignore_missing="yes">broken.conf</include>
+</fontconfig>
+"#,
+    )
+    .unwrap();
+    std::fs::write(dir.join("broken.conf"), "<fontconfig>").unwrap();
+
+    let mut c = FontConfig::default();
+    let mut diagnostics = Diagnostics::new();
+    let ret = c.merge_config_with_diagnostics(&path, &mut diagnostics);
+    std::fs::remove_dir_all(&dir).unwrap();
+    ret.unwrap();
+
+    // missing file is ignored but malformed one is still reported
+    let found: Vec<_> = diagnostics
+        .iter()
+        .map(|d| {
+            (
+                d.severity,
+                d.location
+                    .file
+                    .as_ref()
+                    .unwrap()
+                    .file_name()
+                    .unwrap()
+                    .to_owned(),
+            )
+        })
+        .collect();
+    k9::assert_equal!(found, vec![(Severity::Error, "broken.conf".into())]);
+    assert!(matches!(
+        diagnostics.iter().next().unwrap().error.kind(),
+        Error::Xml(_)
+    ));
+}
+
+#[test]
+fn merge_strict() {
+    let mut c = FontConfig {
+        parse_options: ParseOptions { strict: true },
+        ..Default::default()
+    };
+    let mut diagnostics = Diagnostics::new();
+    c.merge_config_with_diagnostics("./test-conf/fonts.conf", &mut diagnostics)
+        .unwrap();
+
+    // `.conf.json` fixtures in conf.d are not xml, but every config is valid
+    for d in diagnostics.iter() {
+        assert!(matches!(d.error.kind(), Error::Xml(_)), "{}", d);
+    }
+    assert!(c.matches.len() > 10);
 }
@@ -0,0 +1,98 @@
+use fontconfig_parser::*;
+
+#[test]
+fn substitute_full() {
+    let mut c = FontConfig::default();
+    c.merge_config("./test-conf/fonts.conf").unwrap();
+
+    let mut pat = Pattern::new();
+    pat.add(PropertyKind::Family, "mono".into(), EditBinding::Strong);
+    c.substitute(&mut pat);
+
+    // fonts.conf: Accept deprecated 'mono' alias, replacing it with 'monospace'
+    // 52-nixos-default-fonts.conf: alias prefers 'DejaVu Sans Mono' with the same binding
+    let families = pat.get(&PropertyKind::Family).unwrap();
+    assert_eq!(
+        families.first(),
+        Some(&(
+            Value::String("DejaVu Sans Mono".into()),
+            EditBinding::Strong
+        ))
+    );
+    assert!(families.contains(&(Value::String("monospace".into()), EditBinding::Strong)));
+}
+
+#[test]
+fn substitute_scan_full() {
+    let mut c = FontConfig::default();
+    c.merge_config("./test-conf/fonts.conf").unwrap();
+
+    // 80-delicious.conf: Delicious 'heavy' variant says its Medium weight
+    let mut font: Pattern = vec![
+        (PropertyKind::Family, Value::from("Delicious")),
+        (PropertyKind::Style, Value::from("Heavy")),
+        (PropertyKind::Weight, Value::Int(100)),
+    ]
+    .into_iter()
+    .collect();
+    c.substitute_scan(&mut font);
+    assert_eq!(
+        font.get_value(&PropertyKind::Weight),
+        Some(&Value::Int(210))
+    );
+
+    // 65-fonts-persian.conf: family is compared ignoring blanks
+    let mut font: Pattern = vec![(PropertyKind::Family, Value::from("Ro ya"))]
+        .into_iter()
+        .collect();
+    c.substitute_scan(&mut font);
+    assert_eq!(
+        font.get_value(&PropertyKind::Foundry),
+        Some(&Value::from("farsiweb"))
+    );
+
+    // `<match target="pattern">` and `<alias>` are not applied
+    let mut font: Pattern = vec![(PropertyKind::Family, Value::from("mono"))]
+        .into_iter()
+        .collect();
+    let expected = font.clone();
+    c.substitute_scan(&mut font);
+    assert_eq!(font, expected);
+}
+
+#[test]
+fn render_settings_full() {
+    let mut c = FontConfig::default();
+    c.merge_config("./test-conf/fonts.conf").unwrap();
+
+    let mut pat = Pattern::new();
+    pat.add(
+        PropertyKind::Family,
+        "Bitstream Vera Sans".into(),
+        EditBinding::Strong,
+    );
+    pat.add(
+        PropertyKind::PixelSize,
+        Value::Double(7.0),
+        EditBinding::Strong,
+    );
+    c.substitute(&mut pat);
+    pat.default_substitute();
+
+    let font: Pattern = vec![
+        (PropertyKind::Family, Value::from("Bitstream Vera Sans")),
+        (PropertyKind::Outline, Value::Bool(true)),
+    ]
+    .into_iter()
+    .collect();
+
+    let settings = c.render_settings(&pat, &font);
+
+    // 10-nixos-rendering.conf
+    assert_eq!(settings.hint_style, HintStyle::Slight);
+    assert_eq!(settings.rgba, Rgba::Rgb);
+    assert_eq!(settings.lcd_filter, LcdFilter::Default);
+    assert!(settings.antialias);
+    // 20-unhint-small-vera.conf
+    assert!(!settings.hinting);
+}
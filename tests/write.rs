@@ -0,0 +1,59 @@
+use fontconfig_parser::*;
+
+#[test]
+fn write_roundtrip_conf() -> Result<()> {
+    let mut paths = vec!["./test-conf/fonts.conf".into()];
+    paths.extend(
+        glob::glob("./test-conf/conf.d/*.conf")
+            .unwrap()
+            .map(|p| p.unwrap()),
+    );
+
+    for path in paths {
+        let parts = parse_config_parts(&std::fs::read_to_string(&path)?)?;
+        let written = write_config(&parts);
+
+        k9::assert_equal!(parse_config_parts(&written)?, parts, "{}", path.display());
+        // valid against `fonts.dtd`
+        parse_config_parts_with_options(&written, &ParseOptions { strict: true })?;
+
+        let source = std::fs::read_to_string(&path)?;
+        let formatted = format_config(&source)?;
+        k9::assert_equal!(format_config(&formatted)?, formatted, "{}", path.display());
+        // every comment is kept
+        k9::assert_equal!(
+            formatted.matches("<!--").count(),
+            source.matches("<!--").count(),
+            "{}",
+            path.display()
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn write_font_config() {
+    let mut c = FontConfig::default();
+    c.merge_config("./test-conf/fonts.conf").unwrap();
+
+    k9::assert_equal!(
+        parse_config_parts(&c.to_xml()).unwrap(),
+        c.to_config_parts()
+    );
+
+    // aliases and matches keep their relative order
+    let rules: Vec<_> = c
+        .rules
+        .iter()
+        .map(|r| &r.part)
+        .filter(|p| matches!(p, ConfigPart::Alias(_) | ConfigPart::Match(_)))
+        .cloned()
+        .collect();
+    let parts: Vec<_> = c
+        .to_config_parts()
+        .into_iter()
+        .filter(|p| matches!(p, ConfigPart::Alias(_) | ConfigPart::Match(_)))
+        .collect();
+    k9::assert_equal!(parts, rules);
+}